@@ -0,0 +1,63 @@
+//! Checks that the core and `alloc` macros build without `std`
+//!
+//! Build with `cargo build --target thumbv7em-none-eabihf` so `std` can't be
+//! linked in by accident.
+#![no_std]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use try_utils::{
+    try_add, try_break, try_collect_return, try_continue, try_ensure, try_guard, try_located,
+    try_return, try_return_boxed_impl, ContextStack, DisplayError, GuardError, UserError,
+};
+
+pub fn core_guards(vals: &[Option<u32>]) -> Option<u32> {
+    let mut total = 0u32;
+    for val in vals {
+        let val = try_continue!(*val);
+        total = try_add!(total, val, None);
+    }
+    for val in vals {
+        total += try_break!(*val);
+    }
+    let first = try_return!(vals.first(), None);
+    let _ = try_guard!(0, *first, None);
+    Some(total)
+}
+
+pub fn located(val: Option<u32>) -> Result<u32, GuardError> {
+    try_located!(val)
+}
+
+pub fn ensured(x: i32) -> Result<i32, String> {
+    try_ensure!(x > 0, "expected {} to be positive", x; Err);
+    Ok(x)
+}
+
+pub fn displayed(val: Option<u32>) -> Result<u32, DisplayError> {
+    let val = try_return!(val, display: |f| f.write_str("missing"));
+    Ok(val)
+}
+
+pub fn user_msg(val: Option<u32>) -> Result<u32, UserError> {
+    let val = try_return!(val, user_msg: "missing");
+    Ok(val)
+}
+
+pub fn with_ctx(val: Option<u32>, stack: &mut ContextStack) -> Option<u32> {
+    let val = try_return!(val, push_ctx: "loading", stack, None);
+    Some(val)
+}
+
+pub fn collected(input: &[&str]) -> Result<Vec<u32>, core::num::ParseIntError> {
+    Ok(try_collect_return!(input.iter().map(|s| s.parse::<u32>())))
+}
+
+pub fn boxed(val: Option<u32>) -> Box<dyn Iterator<Item = u32>> {
+    let val = try_return_boxed_impl!(val, core::iter::empty());
+    Box::new(core::iter::once(val))
+}
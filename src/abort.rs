@@ -0,0 +1,88 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::panic::Location;
+
+#[doc(hidden)]
+#[cold]
+#[track_caller]
+pub fn __abort(msg: &dyn fmt::Display) -> ! {
+    let line = format!("{} at {}\n", msg, Location::caller());
+    // the process is going away, so a failed write can't be reported
+    let _ = io::stderr().write_all(line.as_bytes());
+    std::process::abort()
+}
+
+/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
+/// prints the given message with the location of the guard to stderr and
+/// aborts the process.
+///
+/// Nothing is unwound and no destructors run. This is for invariants that
+/// must never fail, in builds with `panic = "abort"` where panicking would
+/// abort anyway.
+///
+/// ```
+/// use try_utils::try_abort;
+///
+/// let config: Option<u32> = Some(8);
+/// let workers = try_abort!(config, "fatal: no worker count");
+/// assert_eq!(workers, 8);
+/// ```
+#[macro_export]
+macro_rules! try_abort {
+    ($e: expr, $msg: expr $(,)?) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => $crate::__abort(&$msg),
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    fn is_child() -> bool {
+        std::env::var_os("TRY_UTILS_CHILD").is_some()
+    }
+
+    fn workers(val: Result<u32, ()>) -> u32 {
+        try_abort!(val, "fatal: guard failed")
+    }
+
+    #[test]
+    fn try_abort_child() {
+        if !is_child() {
+            return;
+        }
+        assert_eq!(workers(Ok(4)), 4);
+        eprintln!("passed ok");
+        workers(Err(()));
+        eprintln!("still running");
+    }
+
+    #[test]
+    fn try_abort() {
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "abort::tests::try_abort_child",
+                "--nocapture",
+                "--test-threads=1",
+            ])
+            .env("TRY_UTILS_CHILD", "1")
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            assert_eq!(output.status.signal(), Some(6));
+        }
+
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("passed ok\n"));
+        assert!(stderr.contains(&format!("fatal: guard failed at {}:", file!())));
+        assert!(!stderr.contains("still running"));
+    }
+}
@@ -0,0 +1,76 @@
+/// Yields the value of an expression from the current coroutine if it is
+/// `Some` or `Ok`, otherwise returns from the coroutine with the given value
+/// or `()` if none is given.
+///
+/// Coroutines are unstable, so this needs the `nightly` feature and a nightly
+/// compiler with `#![feature(coroutines)]` enabled. The macro evaluates to the
+/// value the coroutine is resumed with.
+///
+/// ```
+/// #![feature(coroutines, coroutine_trait, stmt_expr_attributes)]
+///
+/// use std::ops::{Coroutine, CoroutineState};
+/// use std::pin::Pin;
+/// use try_utils::try_yield_return;
+///
+/// let mut co = #[coroutine]
+/// || {
+///     for val in [Some(1), None, Some(3)] {
+///         try_yield_return!(val, "missing");
+///     }
+///     "done"
+/// };
+/// assert_eq!(Pin::new(&mut co).resume(()), CoroutineState::Yielded(1));
+/// assert_eq!(Pin::new(&mut co).resume(()), CoroutineState::Complete("missing"));
+/// ```
+#[macro_export]
+macro_rules! try_yield_return {
+    ($e: expr) => {
+        $crate::try_yield_return!($e, ())
+    };
+
+    ($e: expr, $ret: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => yield v,
+            None => return $ret,
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::{Coroutine, CoroutineState};
+    use std::pin::Pin;
+
+    fn drain<C: Coroutine<Yield = u32> + Unpin>(mut co: C) -> (Vec<u32>, C::Return) {
+        let mut yielded = Vec::new();
+        loop {
+            match Pin::new(&mut co).resume(()) {
+                CoroutineState::Yielded(v) => yielded.push(v),
+                CoroutineState::Complete(ret) => return (yielded, ret),
+            }
+        }
+    }
+
+    #[test]
+    fn try_yield_return_coroutine() {
+        let vals = vec![Ok(1), Ok(2), Err("bad"), Ok(4)];
+        let co = #[coroutine]
+        move || {
+            for val in vals {
+                try_yield_return!(val, Err(()));
+            }
+            Ok(())
+        };
+        assert_eq!(drain(co), (vec![1, 2], Err(())));
+
+        let co = #[coroutine]
+        || {
+            for val in [Some(1), Some(2)] {
+                try_yield_return!(val);
+            }
+        };
+        assert_eq!(drain(co), (vec![1, 2], ()));
+    }
+}
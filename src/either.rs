@@ -0,0 +1,147 @@
+use core::iter::FusedIterator;
+
+/// An iterator that is one of two iterators with the same item type
+///
+/// This is returned by [`try_or_iter!`](crate::try_or_iter) so both of its
+/// paths have the same type.
+///
+/// ```
+/// use try_utils::Either;
+///
+/// fn evens(all: bool) -> Either<std::ops::Range<u32>, std::iter::StepBy<std::ops::Range<u32>>> {
+///     if all {
+///         Either::Left(0..4)
+///     } else {
+///         Either::Right((0..4).step_by(2))
+///     }
+/// }
+/// assert_eq!(evens(true).collect::<Vec<_>>(), [0, 1, 2, 3]);
+/// assert_eq!(evens(false).collect::<Vec<_>>(), [0, 2]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Either<A, B> {
+    /// The first iterator
+    Left(A),
+    /// The second iterator
+    Right(B),
+}
+
+impl<A, B> Iterator for Either<A, B>
+where
+    A: Iterator,
+    B: Iterator<Item = A::Item>,
+{
+    type Item = A::Item;
+
+    fn next(&mut self) -> Option<A::Item> {
+        match self {
+            Either::Left(a) => a.next(),
+            Either::Right(b) => b.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Either::Left(a) => a.size_hint(),
+            Either::Right(b) => b.size_hint(),
+        }
+    }
+}
+
+impl<A, B> DoubleEndedIterator for Either<A, B>
+where
+    A: DoubleEndedIterator,
+    B: DoubleEndedIterator<Item = A::Item>,
+{
+    fn next_back(&mut self) -> Option<A::Item> {
+        match self {
+            Either::Left(a) => a.next_back(),
+            Either::Right(b) => b.next_back(),
+        }
+    }
+}
+
+impl<A, B> ExactSizeIterator for Either<A, B>
+where
+    A: ExactSizeIterator,
+    B: ExactSizeIterator<Item = A::Item>,
+{
+}
+
+impl<A, B> FusedIterator for Either<A, B>
+where
+    A: FusedIterator,
+    B: FusedIterator<Item = A::Item>,
+{
+}
+
+/// Evaluates to an iterator over the value of an expression if it is `Some`
+/// or `Ok`, otherwise to the given fallback iterator.
+///
+/// Both paths are an [`Either`], so the guard can be the tail of a function
+/// returning `impl Iterator`.
+///
+/// ```
+/// use try_utils::try_or_iter;
+///
+/// fn ids(s: &str) -> impl Iterator<Item = u32> {
+///     try_or_iter!(s.parse::<u32>(), [0, 0])
+/// }
+/// assert_eq!(ids("7").collect::<Vec<_>>(), [7]);
+/// assert_eq!(ids("x").collect::<Vec<_>>(), [0, 0]);
+/// ```
+///
+/// [`Either`]: crate::Either
+#[macro_export]
+macro_rules! try_or_iter {
+    ($e: expr, $fallback: expr $(,)?) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            ::core::option::Option::Some(v) => $crate::Either::Left(::core::iter::once(v)),
+            ::core::option::Option::None => {
+                $crate::Either::Right(::core::iter::IntoIterator::into_iter($fallback))
+            }
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(key: &str) -> Option<u32> {
+        match key {
+            "one" => Some(1),
+            "two" => Some(2),
+            _ => None,
+        }
+    }
+
+    fn values(key: &str) -> impl DoubleEndedIterator<Item = u32> + ExactSizeIterator {
+        try_or_iter!(lookup(key), 10..13)
+    }
+
+    #[test]
+    fn try_or_iter_paths() {
+        let left: Either<_, core::ops::Range<u32>> = try_or_iter!(lookup("one"), 10..13);
+        assert!(matches!(left, Either::Left(_)));
+
+        assert_eq!(values("two").collect::<Vec<_>>(), [2]);
+        assert_eq!(values("two").len(), 1);
+        assert_eq!(values("three").collect::<Vec<_>>(), [10, 11, 12]);
+        assert_eq!(values("three").rev().collect::<Vec<_>>(), [12, 11, 10]);
+        assert_eq!(values("three").len(), 3);
+    }
+
+    #[test]
+    fn try_or_iter_lazy_fallback() {
+        let mut calls = 0;
+        let mut fallback = || {
+            calls += 1;
+            core::iter::empty()
+        };
+        assert_eq!(try_or_iter!(Ok::<_, ()>(5), fallback()).sum::<u32>(), 5);
+        assert_eq!(try_or_iter!(Err::<u32, _>(()), fallback()).sum::<u32>(), 0);
+        assert_eq!(calls, 1);
+    }
+}
@@ -0,0 +1,166 @@
+/// Does nothing if a condition holds, otherwise formats a message and passes
+/// it to a closure whose result is returned from the current function.
+///
+/// The message uses `format!` syntax and is separated from the closure by a
+/// `;`. The format arguments are only evaluated if the condition is false.
+/// To return a plain value, pass a closure ignoring the message, such as
+/// `|_| false`.
+///
+/// ```
+/// use try_utils::try_ensure;
+///
+/// fn check(x: i32) -> Result<i32, String> {
+///     try_ensure!(x > 0, "expected {} to be positive", x; Err);
+///     Ok(x)
+/// }
+/// assert_eq!(check(10), Ok(10));
+/// assert_eq!(check(-1), Err("expected -1 to be positive".to_owned()));
+///
+/// fn is_small(x: i32) -> bool {
+///     try_ensure!(x < 10, "{} is too big", x; |_| false);
+///     true
+/// }
+/// assert!(is_small(1));
+/// assert!(!is_small(20));
+/// ```
+#[macro_export]
+macro_rules! try_ensure {
+    ($cond: expr, $fmt: literal $(, $arg: expr)* $(,)? ; $ret: expr) => {
+        if !$cond {
+            return $crate::__call($crate::__alloc::format!($fmt $(, $arg)*), $ret);
+        }
+    };
+}
+
+/// Does nothing if a condition holds, otherwise continues the current loop.
+///
+/// A formatted message and a closure can be given like [`try_ensure!`], in
+/// which case the closure is run with the message before continuing, for
+/// example to log it. The format arguments are only evaluated if the
+/// condition is false.
+///
+/// ```
+/// use try_utils::try_ensure_continue;
+///
+/// let mut skipped = Vec::new();
+/// let mut kept = Vec::new();
+/// for x in [1, -2, 3] {
+///     try_ensure_continue!(x > 0, "skipping {}", x; |msg| skipped.push(msg));
+///     kept.push(x);
+/// }
+/// assert_eq!(kept, vec![1, 3]);
+/// assert_eq!(skipped, vec!["skipping -2".to_owned()]);
+/// ```
+///
+/// [`try_ensure!`]: crate::try_ensure
+#[macro_export]
+macro_rules! try_ensure_continue {
+    ($cond: expr) => {
+        if !$cond {
+            continue;
+        }
+    };
+
+    ($cond: expr, $fmt: literal $(, $arg: expr)* $(,)? ; $handler: expr) => {
+        if !$cond {
+            $crate::__call($crate::__alloc::format!($fmt $(, $arg)*), $handler);
+            continue;
+        }
+    };
+}
+
+/// Does nothing if a condition holds, otherwise breaks the current loop.
+///
+/// A formatted message and a closure can be given like [`try_ensure!`], in
+/// which case the closure is run with the message before breaking. The format
+/// arguments are only evaluated if the condition is false.
+///
+/// ```
+/// use try_utils::try_ensure_break;
+///
+/// let mut reason = None;
+/// let mut kept = Vec::new();
+/// for x in [1, -2, 3] {
+///     try_ensure_break!(x > 0, "stopped at {}", x; |msg| reason = Some(msg));
+///     kept.push(x);
+/// }
+/// assert_eq!(kept, vec![1]);
+/// assert_eq!(reason.unwrap(), "stopped at -2");
+/// ```
+///
+/// [`try_ensure!`]: crate::try_ensure
+#[macro_export]
+macro_rules! try_ensure_break {
+    ($cond: expr) => {
+        if !$cond {
+            break;
+        }
+    };
+
+    ($cond: expr, $fmt: literal $(, $arg: expr)* $(,)? ; $handler: expr) => {
+        if !$cond {
+            $crate::__call($crate::__alloc::format!($fmt $(, $arg)*), $handler);
+            break;
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    #[test]
+    fn try_ensure_lazy_message() {
+        fn check(x: u32, formats: &Cell<u32>) -> Result<u32, String> {
+            let counted = |x: u32| {
+                formats.set(formats.get() + 1);
+                x
+            };
+            try_ensure!(x < 10, "expected {} < {}", counted(x), 10; Err);
+            Ok(x)
+        }
+
+        let formats = Cell::new(0);
+        assert_eq!(check(1, &formats), Ok(1));
+        assert_eq!(formats.get(), 0);
+        assert_eq!(check(20, &formats), Err("expected 20 < 10".to_owned()));
+        assert_eq!(formats.get(), 1);
+    }
+
+    #[test]
+    fn try_ensure_plain_value() {
+        fn ratio(a: u32, b: u32) -> Option<u32> {
+            try_ensure!(b != 0, "dividing {} by zero", a; |_| None);
+            Some(a / b)
+        }
+
+        assert_eq!(ratio(6, 3), Some(2));
+        assert_eq!(ratio(6, 0), None);
+    }
+
+    #[test]
+    fn try_ensure_loops() {
+        let mut kept = Vec::new();
+        for x in 0..6 {
+            try_ensure_continue!(x % 2 == 0);
+            kept.push(x);
+        }
+        assert_eq!(kept, vec![0, 2, 4]);
+
+        let mut kept = Vec::new();
+        for x in 0..6 {
+            try_ensure_break!(x < 3);
+            kept.push(x);
+        }
+        assert_eq!(kept, vec![0, 1, 2]);
+
+        let mut messages = Vec::new();
+        for x in 0..4 {
+            try_ensure_continue!(x != 1, "skipped {}", x,; |msg| messages.push(msg));
+        }
+        for x in 0..4 {
+            try_ensure_break!(x != 2, "broke at {}", x; |msg| messages.push(msg));
+        }
+        assert_eq!(messages, vec!["skipped 1", "broke at 2"]);
+    }
+}
@@ -0,0 +1,98 @@
+#[doc(hidden)]
+pub fn __parse_bool(val: &str) -> Option<bool> {
+    let val = val.trim();
+    if ["1", "true", "yes", "on"]
+        .iter()
+        .any(|s| val.eq_ignore_ascii_case(s))
+    {
+        Some(true)
+    } else if ["0", "false", "no", "off"]
+        .iter()
+        .any(|s| val.eq_ignore_ascii_case(s))
+    {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Reads an environment variable as a boolean, yielding the given default if
+/// it is unset or not a boolean.
+///
+/// `1`, `true`, `yes` and `on` are `true` and `0`, `false`, `no` and `off`
+/// are `false`, ignoring case and surrounding whitespace.
+///
+/// A third argument can be given to return from the current function with
+/// that value when the variable is set to something that isn't a boolean.
+///
+/// ```
+/// use try_utils::try_bool_env;
+///
+/// std::env::set_var("MY_APP_ENABLE_X", "yes");
+/// assert!(try_bool_env!("MY_APP_ENABLE_X", false));
+/// assert!(!try_bool_env!("MY_APP_ENABLE_Y", false));
+///
+/// fn enabled() -> Result<bool, ()> {
+///     Ok(try_bool_env!("MY_APP_ENABLE_Z", false, Err(())))
+/// }
+/// std::env::set_var("MY_APP_ENABLE_Z", "maybe");
+/// assert_eq!(enabled(), Err(()));
+/// ```
+#[macro_export]
+macro_rules! try_bool_env {
+    ($name: expr, $default: expr) => {
+        match ::std::env::var($name) {
+            ::core::result::Result::Ok(v) => $crate::__parse_bool(&v).unwrap_or($default),
+            ::core::result::Result::Err(_) => $default,
+        }
+    };
+
+    ($name: expr, $default: expr, $ret: expr) => {
+        match ::std::env::var($name) {
+            ::core::result::Result::Ok(v) => match $crate::__parse_bool(&v) {
+                ::core::option::Option::Some(b) => b,
+                ::core::option::Option::None => return $ret,
+            },
+            ::core::result::Result::Err(_) => $default,
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    #[test]
+    fn try_bool_env_set() {
+        for val in ["1", "true", "YES", " on "] {
+            env::set_var("TRY_UTILS_TEST_SET", val);
+            assert!(try_bool_env!("TRY_UTILS_TEST_SET", false));
+        }
+        for val in ["0", "False", "no", "off"] {
+            env::set_var("TRY_UTILS_TEST_SET", val);
+            assert!(!try_bool_env!("TRY_UTILS_TEST_SET", true));
+        }
+    }
+
+    #[test]
+    fn try_bool_env_unset() {
+        env::remove_var("TRY_UTILS_TEST_UNSET");
+        assert!(try_bool_env!("TRY_UTILS_TEST_UNSET", true));
+        assert!(!try_bool_env!("TRY_UTILS_TEST_UNSET", false));
+    }
+
+    #[test]
+    fn try_bool_env_invalid() {
+        fn strict() -> Option<bool> {
+            Some(try_bool_env!("TRY_UTILS_TEST_INVALID", false, None))
+        }
+
+        env::set_var("TRY_UTILS_TEST_INVALID", "maybe");
+        assert!(try_bool_env!("TRY_UTILS_TEST_INVALID", true));
+        assert!(!try_bool_env!("TRY_UTILS_TEST_INVALID", false));
+        assert_eq!(strict(), None);
+
+        env::set_var("TRY_UTILS_TEST_INVALID", "1");
+        assert_eq!(strict(), Some(true));
+    }
+}
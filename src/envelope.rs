@@ -0,0 +1,102 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// A serializable error with a status code, for returning a consistent error
+/// shape from APIs
+///
+/// This is the error built by `try_return!(expr, envelope: (code, "msg"))`.
+/// `details` is left out when serializing if it is empty.
+///
+/// ```
+/// use try_utils::GuardErrorEnvelope;
+///
+/// let err = GuardErrorEnvelope::new(404, "user not found").with_detail("id: 7");
+/// assert_eq!(err.to_string(), "404: user not found");
+/// assert_eq!(err.details, vec!["id: 7".to_owned()]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuardErrorEnvelope {
+    /// A status code, such as an HTTP status
+    pub code: u16,
+    /// What went wrong
+    pub message: Cow<'static, str>,
+    /// Extra information about the failure
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<String>,
+}
+
+impl GuardErrorEnvelope {
+    /// Creates a new envelope with the given code and message and no details
+    pub fn new(code: u16, message: impl Into<Cow<'static, str>>) -> Self {
+        GuardErrorEnvelope {
+            code,
+            message: message.into(),
+            details: Vec::new(),
+        }
+    }
+
+    /// Adds a detail to the envelope
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.details.push(detail.into());
+        self
+    }
+}
+
+impl fmt::Display for GuardErrorEnvelope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl core::error::Error for GuardErrorEnvelope {}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::GuardErrorEnvelope;
+    use crate::try_return;
+
+    #[test]
+    fn try_return_envelope_json() {
+        fn find(id: Option<u32>) -> Result<u32, GuardErrorEnvelope> {
+            let id = try_return!(id, envelope: (404, "user not found"));
+            Ok(id)
+        }
+
+        assert_eq!(find(Some(7)), Ok(7));
+        let err = find(None).unwrap_err();
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json, json!({"code": 404, "message": "user not found"}));
+
+        let err = err.with_detail("id").with_detail("missing");
+        let json = serde_json::to_string(&err).unwrap();
+        assert_eq!(
+            json,
+            r#"{"code":404,"message":"user not found","details":["id","missing"]}"#
+        );
+        let back: GuardErrorEnvelope = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, err);
+    }
+
+    #[test]
+    fn try_return_envelope_status() {
+        fn handler(id: Result<u32, ()>, name: &str) -> (u16, Value) {
+            let id = try_return!(id, envelope: (400, format!("bad id for {}", name)), |err| {
+                (err.code, serde_json::to_value(err).unwrap())
+            });
+            (200, json!({ "id": id }))
+        }
+
+        assert_eq!(handler(Ok(1), "a"), (200, json!({"id": 1})));
+        let (status, body) = handler(Err(()), "a");
+        assert_eq!(status, 400);
+        assert_eq!(body["code"], 400);
+        assert_eq!(body["message"], "bad id for a");
+        assert!(body.get("details").is_none());
+    }
+}
@@ -0,0 +1,186 @@
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+type DisplayFn = dyn Fn(&mut fmt::Formatter<'_>) -> fmt::Result + Send + Sync;
+
+/// An error whose message is produced by a closure when it is displayed
+///
+/// This is the error returned by `try_return!(expr, display: |f| ...)`. The
+/// message is only formatted when the error is actually displayed, so no
+/// string is built on the failure path.
+///
+/// ```
+/// use try_utils::DisplayError;
+///
+/// let err = DisplayError::new(|f| write!(f, "failed: {}", 10));
+/// assert_eq!(err.to_string(), "failed: 10");
+/// ```
+pub struct DisplayError(Box<DisplayFn>);
+
+impl DisplayError {
+    /// Creates a new error which displays using the given closure
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&mut fmt::Formatter<'_>) -> fmt::Result + Send + Sync + 'static,
+    {
+        DisplayError(Box::new(f))
+    }
+}
+
+impl fmt::Display for DisplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (self.0)(f)
+    }
+}
+
+impl fmt::Debug for DisplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DisplayError(\"")?;
+        (self.0)(f)?;
+        f.write_str("\")")
+    }
+}
+
+impl core::error::Error for DisplayError {}
+
+/// An error carrying a message suitable for showing to end users
+///
+/// This is the error returned by `try_return!(expr, user_msg: "...")`.
+///
+/// ```
+/// use try_utils::UserError;
+///
+/// let err = UserError::new("Please provide a valid value");
+/// assert_eq!(err.to_string(), "Please provide a valid value");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UserError(pub Cow<'static, str>);
+
+impl UserError {
+    /// Creates a new error with the given message
+    pub fn new(msg: impl Into<Cow<'static, str>>) -> Self {
+        UserError(msg.into())
+    }
+
+    /// Returns the user facing message
+    pub fn message(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for UserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl core::error::Error for UserError {}
+
+impl From<UserError> for Cow<'static, str> {
+    fn from(err: UserError) -> Self {
+        err.0
+    }
+}
+
+impl From<UserError> for String {
+    fn from(err: UserError) -> Self {
+        err.0.into_owned()
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<UserError> for io::Error {
+    fn from(err: UserError) -> Self {
+        io::Error::other(err)
+    }
+}
+
+/// A trail of context messages built up by nested guards
+///
+/// `try_return!(expr, push_ctx: "msg", stack, ret)` pushes onto the stack as a
+/// failure propagates outwards, so the innermost context is first. Displaying
+/// the stack shows the outermost context first, joined with `: `.
+///
+/// ```
+/// use try_utils::ContextStack;
+///
+/// let mut stack = ContextStack::new();
+/// stack.push("parsing id");
+/// stack.push("loading user");
+/// assert_eq!(stack.to_string(), "loading user: parsing id");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ContextStack(Vec<String>);
+
+impl ContextStack {
+    /// Creates an empty stack
+    pub fn new() -> Self {
+        ContextStack(Vec::new())
+    }
+
+    /// Pushes a context message onto the stack
+    pub fn push(&mut self, ctx: impl Into<String>) {
+        self.0.push(ctx.into());
+    }
+
+    /// Returns the context messages, innermost first
+    pub fn as_slice(&self) -> &[String] {
+        &self.0
+    }
+
+    /// Returns the number of context messages
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if there are no context messages
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Display for ContextStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, ctx) in self.0.iter().rev().enumerate() {
+            if i > 0 {
+                f.write_str(": ")?;
+            }
+            f.write_str(ctx)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::error::Error for ContextStack {}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "std")]
+    use std::io;
+
+    use super::{DisplayError, UserError};
+
+    #[test]
+    fn display_error_debug() {
+        let err = DisplayError::new(|f| write!(f, "bad value {}", 3));
+        assert_eq!(format!("{:?}", err), "DisplayError(\"bad value 3\")");
+    }
+
+    #[test]
+    fn user_error_conversions() {
+        let err = UserError::new(format!("bad {}", "input"));
+        assert_eq!(String::from(err.clone()), "bad input");
+
+        #[cfg(feature = "std")]
+        {
+            let err = io::Error::from(err);
+            assert_eq!(err.kind(), io::ErrorKind::Other);
+            assert_eq!(err.to_string(), "bad input");
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::process::{ExitCode, Termination};
+
+/// An exit status for `main`, returned by `try_return!(expr, exit: code)`
+///
+/// ```
+/// use try_utils::{try_return, ExitWith};
+///
+/// fn main() -> ExitWith {
+///     let val: u32 = try_return!("10".parse().ok(), exit: 2);
+///     assert_eq!(val, 10);
+///     ExitWith::SUCCESS
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExitWith(pub u8);
+
+impl ExitWith {
+    /// The exit status for success
+    pub const SUCCESS: ExitWith = ExitWith(0);
+    /// The exit status for a general failure
+    pub const FAILURE: ExitWith = ExitWith(1);
+}
+
+impl Termination for ExitWith {
+    fn report(self) -> ExitCode {
+        self.into()
+    }
+}
+
+impl From<ExitWith> for ExitCode {
+    fn from(exit: ExitWith) -> Self {
+        ExitCode::from(exit.0)
+    }
+}
+
+#[doc(hidden)]
+pub fn __termination<T: Termination>(term: T) -> T {
+    term
+}
+
+/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
+/// returns the given [`Termination`] value from the current function.
+///
+/// This is `try_return!(expr, exit: code)` for `main`-like functions
+/// returning any `Termination` type instead of an exit code.
+///
+/// ```
+/// use std::process::ExitCode;
+/// use try_utils::try_term;
+///
+/// fn main() -> ExitCode {
+///     let val: u32 = try_term!("10".parse(), ExitCode::from(2));
+///     assert_eq!(val, 10);
+///     ExitCode::SUCCESS
+/// }
+/// ```
+#[macro_export]
+macro_rules! try_term {
+    ($e: expr, $term: expr $(,)?) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => return $crate::__termination($term),
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::process::{ExitCode, Termination};
+
+    use super::ExitWith;
+    use crate::try_return;
+
+    #[test]
+    fn try_return_exit() {
+        fn run(arg: Option<&str>) -> ExitWith {
+            let arg = try_return!(arg, exit: 2);
+            let _: u32 = try_return!(arg.parse(), exit: 3);
+            ExitWith::SUCCESS
+        }
+
+        fn run_code(arg: Option<&str>) -> ExitCode {
+            let _ = try_return!(arg, exit: 4);
+            ExitCode::SUCCESS
+        }
+
+        assert_eq!(run(Some("10")), ExitWith::SUCCESS);
+        assert_eq!(run(None), ExitWith(2));
+        assert_eq!(run(Some("x")), ExitWith(3));
+        assert_eq!(run(None).report(), ExitCode::from(2));
+        assert_eq!(run_code(None), ExitCode::from(4));
+        assert_eq!(run_code(Some("")), ExitCode::SUCCESS);
+    }
+
+    thread_local! {
+        static REPORTED: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Outcome {
+        Done,
+        Usage(&'static str),
+    }
+
+    impl Termination for Outcome {
+        fn report(self) -> ExitCode {
+            let (status, code) = match self {
+                Outcome::Done => ("done", 0),
+                Outcome::Usage(msg) => (msg, 64),
+            };
+            REPORTED.with(|reported| reported.borrow_mut().push(status));
+            ExitCode::from(code)
+        }
+    }
+
+    #[test]
+    fn try_term_custom() {
+        fn run(args: &[&str]) -> Outcome {
+            let cmd = try_term!(args.first(), Outcome::Usage("missing command"));
+            let _: u8 = try_term!(cmd.parse(), Outcome::Usage("bad command"));
+            Outcome::Done
+        }
+
+        assert_eq!(run(&["1"]), Outcome::Done);
+        assert_eq!(run(&[]), Outcome::Usage("missing command"));
+        assert_eq!(run(&["x"]).report(), ExitCode::from(64));
+        assert_eq!(run(&["2"]).report(), ExitCode::SUCCESS);
+        assert_eq!(run(&[]).report(), ExitCode::from(64));
+        REPORTED.with(|reported| {
+            assert_eq!(
+                *reported.borrow(),
+                ["bad command", "done", "missing command"]
+            );
+        });
+
+        fn run_result(arg: Option<&str>) -> Result<(), String> {
+            let _ = try_term!(arg, Err("no arg".to_owned()));
+            Ok(())
+        }
+        assert_eq!(run_result(None), Err("no arg".to_owned()));
+        assert!(run_result(Some("a")).is_ok());
+    }
+}
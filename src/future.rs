@@ -0,0 +1,489 @@
+use core::cell::Cell;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll};
+
+/// A future that yields to the executor once before completing
+///
+/// This is the default yield point used by [`try_continue_yield!`]. It wakes
+/// itself when first polled, so it works with any executor.
+///
+/// [`try_continue_yield!`]: crate::try_continue_yield
+#[derive(Debug, Default)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct YieldNow {
+    yielded: bool,
+}
+
+/// Returns a future that yields to the executor once before completing
+pub fn yield_now() -> YieldNow {
+    YieldNow::default()
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            Poll::Ready(())
+        } else {
+            self.yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
+/// awaits a yield point and then continues the current loop.
+///
+/// Awaiting before continuing stops a loop that keeps failing from busy
+/// spinning the executor. The yield point defaults to [`yield_now`], or any
+/// future can be given to use the yield of a specific runtime. An optional
+/// label can be given to continue a loop with a label.
+///
+/// ```
+/// use try_utils::try_continue_yield;
+///
+/// async fn sum(vals: Vec<Option<u32>>) -> u32 {
+///     let mut sum = 0;
+///     for val in vals {
+///         sum += try_continue_yield!(val);
+///     }
+///     sum
+/// }
+/// ```
+#[macro_export]
+macro_rules! try_continue_yield {
+    ($e: expr) => {
+        $crate::try_continue_yield!($e, $crate::yield_now())
+    };
+
+    ($e: expr, $label: lifetime) => {
+        $crate::try_continue_yield!($e, $crate::yield_now(), $label)
+    };
+
+    ($e: expr, $yield: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => {
+                $yield.await;
+                continue;
+            }
+        }
+    }};
+
+    ($e: expr, $yield: expr, $label: lifetime) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => {
+                $yield.await;
+                continue $label;
+            }
+        }
+    }};
+}
+
+/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
+/// pins and boxes the given future and returns it from the current function.
+///
+/// This is for synchronous functions returning `Pin<Box<dyn Future>>`, where
+/// the guard runs before the future is built and the fallback is usually an
+/// `async` block.
+///
+/// ```
+/// use std::future::Future;
+/// use std::pin::Pin;
+/// use try_utils::try_return_fut;
+///
+/// fn fetch(id: Option<u32>) -> Pin<Box<dyn Future<Output = u32>>> {
+///     let id = try_return_fut!(id, async { 0 });
+///     Box::pin(async move { id * 2 })
+/// }
+/// ```
+#[cfg(feature = "alloc")]
+#[macro_export]
+macro_rules! try_return_fut {
+    ($e: expr, $ret: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => return $crate::__alloc::boxed::Box::pin($ret),
+        }
+    }};
+}
+
+/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
+/// returns the given value as the output of the current `async fn` or block,
+/// or `()` if none is given.
+///
+/// This is [`try_return!`] for async code, with `on_fail:` taking a closure
+/// that is run just before returning, for example to record a metric. It
+/// also works in methods desugared by `#[async_trait]`, where the body is
+/// moved into a boxed `async move` block.
+///
+/// ```
+/// use try_utils::try_return_async_fn;
+///
+/// async fn load(id: Option<u32>) -> u32 {
+///     let id = try_return_async_fn!(id, 0, on_fail: || eprintln!("missing id"));
+///     id * 2
+/// }
+/// ```
+///
+/// [`try_return!`]: crate::try_return
+#[macro_export]
+macro_rules! try_return_async_fn {
+    ($e: expr) => {
+        $crate::try_return_async_fn!($e, ())
+    };
+
+    ($e: expr, $ret: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => return $ret,
+        }
+    }};
+
+    ($e: expr, $ret: expr, on_fail: $hook: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => {
+                ($hook)();
+                return $ret;
+            }
+        }
+    }};
+}
+
+/// A trait for cancellation tokens checked by [`try_cancelled!`]
+///
+/// This is implemented for `AtomicBool` and `Cell<bool>`, which are
+/// cancelled when `true`, and can be implemented for the token of any
+/// runtime.
+///
+/// ```
+/// use try_utils::IsCancelled;
+///
+/// struct Token(u32);
+///
+/// impl IsCancelled for Token {
+///     fn is_cancelled(&self) -> bool {
+///         self.0 == 0
+///     }
+/// }
+/// assert!(Token(0).is_cancelled());
+/// ```
+///
+/// [`try_cancelled!`]: crate::try_cancelled
+pub trait IsCancelled {
+    /// Returns `true` if the work this token belongs to should stop
+    fn is_cancelled(&self) -> bool;
+}
+
+impl IsCancelled for AtomicBool {
+    fn is_cancelled(&self) -> bool {
+        self.load(Ordering::Acquire)
+    }
+}
+
+impl IsCancelled for Cell<bool> {
+    fn is_cancelled(&self) -> bool {
+        self.get()
+    }
+}
+
+impl<T: IsCancelled + ?Sized> IsCancelled for &T {
+    fn is_cancelled(&self) -> bool {
+        (**self).is_cancelled()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: IsCancelled + ?Sized> IsCancelled for alloc::sync::Arc<T> {
+    fn is_cancelled(&self) -> bool {
+        (**self).is_cancelled()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: IsCancelled + ?Sized> IsCancelled for alloc::rc::Rc<T> {
+    fn is_cancelled(&self) -> bool {
+        (**self).is_cancelled()
+    }
+}
+
+/// Does nothing if an [`IsCancelled`] token hasn't been cancelled, otherwise
+/// returns from the current function or `async` block with the given value
+/// or `()` if none is given.
+///
+/// This is meant for the top of loop bodies, so long running work stops
+/// between items once it is cancelled.
+///
+/// ```
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// use try_utils::try_cancelled;
+///
+/// async fn process(items: &[u32], token: &AtomicBool) -> Option<u32> {
+///     let mut sum = 0;
+///     for item in items {
+///         try_cancelled!(token, None);
+///         sum += item;
+///     }
+///     Some(sum)
+/// }
+/// ```
+///
+/// [`IsCancelled`]: crate::IsCancelled
+#[macro_export]
+macro_rules! try_cancelled {
+    ($token: expr) => {
+        $crate::try_cancelled!($token, ())
+    };
+
+    ($token: expr, $ret: expr) => {
+        if $crate::IsCancelled::is_cancelled(&$token) {
+            return $ret;
+        }
+    };
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::cell::Cell;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    pub(crate) fn block_on<F: Future>(fut: F) -> F::Output {
+        let mut fut = pin!(fut);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(v) = fut.as_mut().poll(&mut cx) {
+                return v;
+            }
+        }
+    }
+
+    #[test]
+    fn yield_now_pends_once() {
+        let mut fut = pin!(super::yield_now());
+        let mut cx = Context::from_waker(Waker::noop());
+        assert_eq!(fut.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(fut.as_mut().poll(&mut cx), Poll::Ready(()));
+    }
+
+    #[test]
+    fn try_continue_yield_default() {
+        let sum = block_on(async {
+            let mut sum = 0;
+            for val in [Some(1), None, Some(2), None] {
+                sum += try_continue_yield!(val);
+            }
+            sum
+        });
+        assert_eq!(sum, 3);
+    }
+
+    #[test]
+    fn try_continue_yield_custom() {
+        let yields = Cell::new(0);
+        let yielded = || async {
+            yields.set(yields.get() + 1);
+        };
+
+        let sum = block_on(async {
+            let mut sum = 0;
+            for val in [Ok(1), Err(()), Ok(2), Err(()), Err(())] {
+                sum += try_continue_yield!(val, yielded());
+            }
+            sum
+        });
+        assert_eq!(sum, 3);
+        assert_eq!(yields.get(), 3);
+
+        yields.set(0);
+        let count = block_on(async {
+            let mut count = 0;
+            'outer: for _ in 0..4 {
+                count += 1;
+                loop {
+                    let _: u32 = try_continue_yield!(None, yielded(), 'outer);
+                }
+            }
+            count
+        });
+        assert_eq!(count, 4);
+        assert_eq!(yields.get(), 4);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn try_return_fut_boxed() {
+        use std::pin::Pin;
+
+        fn fetch(
+            id: Result<i32, ()>,
+            built: &Cell<bool>,
+        ) -> Pin<Box<dyn Future<Output = i32> + '_>> {
+            let id = try_return_fut!(id, async { -1 });
+            built.set(true);
+            Box::pin(async move { id * 2 })
+        }
+
+        let built = Cell::new(false);
+        assert_eq!(block_on(fetch(Err(()), &built)), -1);
+        assert!(!built.get());
+        assert_eq!(block_on(fetch(Ok(21), &built)), 42);
+        assert!(built.get());
+    }
+
+    #[test]
+    fn try_return_async_fn_paths() {
+        async fn double(id: Option<u32>) -> u32 {
+            let id = try_return_async_fn!(id, 0);
+            yield_twice().await;
+            id * 2
+        }
+
+        async fn yield_twice() {
+            super::yield_now().await;
+            super::yield_now().await;
+        }
+
+        async fn counted(id: Result<u32, ()>, failures: &Cell<u32>) -> Option<u32> {
+            let id = try_return_async_fn!(id, None, on_fail: || failures.set(failures.get() + 1));
+            Some(id)
+        }
+
+        async fn unit(id: Option<u32>, seen: &Cell<u32>) {
+            seen.set(try_return_async_fn!(id));
+        }
+
+        assert_eq!(block_on(double(Some(21))), 42);
+        assert_eq!(block_on(double(None)), 0);
+
+        let failures = Cell::new(0);
+        assert_eq!(block_on(counted(Ok(1), &failures)), Some(1));
+        assert_eq!(failures.get(), 0);
+        assert_eq!(block_on(counted(Err(()), &failures)), None);
+        assert_eq!(failures.get(), 1);
+
+        let seen = Cell::new(0);
+        block_on(unit(None, &seen));
+        assert_eq!(seen.get(), 0);
+        block_on(unit(Some(3), &seen));
+        assert_eq!(seen.get(), 3);
+    }
+
+    mod async_traits {
+        use std::collections::HashMap;
+
+        use async_trait::async_trait;
+
+        use super::block_on;
+        use crate::try_return;
+
+        #[async_trait]
+        pub(super) trait Store {
+            async fn get(&self, key: &str) -> Option<u32>;
+
+            async fn sum(&self, keys: &[&str]) -> Result<u32, String>;
+        }
+
+        #[async_trait(?Send)]
+        pub(super) trait Local {
+            async fn parse(&self, s: &str) -> u32;
+        }
+
+        pub(super) struct Map(pub(super) HashMap<&'static str, u32>);
+
+        #[async_trait]
+        impl Store for Map {
+            async fn get(&self, key: &str) -> Option<u32> {
+                let val = try_return_async_fn!(self.0.get(key), None);
+                crate::yield_now().await;
+                Some(*val)
+            }
+
+            async fn sum(&self, keys: &[&str]) -> Result<u32, String> {
+                let first = try_return!(keys.first(), Err("no keys".to_owned()));
+                try_return_async_fn!(self.get(first).await, Err(format!("missing {}", first)));
+                let mut sum = 0;
+                for key in keys {
+                    sum += try_continue_yield!(self.get(key).await);
+                }
+                Ok(sum)
+            }
+        }
+
+        #[async_trait(?Send)]
+        impl Local for Map {
+            async fn parse(&self, s: &str) -> u32 {
+                let failed = std::cell::Cell::new(false);
+                let val = try_return_async_fn!(s.parse(), 0, on_fail: || failed.set(true));
+                crate::yield_now().await;
+                assert!(!failed.get());
+                val
+            }
+        }
+
+        #[test]
+        fn async_trait_methods() {
+            fn assert_send<T: Send>(_: &T) {}
+
+            let map = Map(HashMap::from([("a", 1), ("b", 2)]));
+            let fut = map.sum(&["a", "x", "b"]);
+            assert_send(&fut);
+            assert_eq!(block_on(fut), Ok(3));
+            assert_eq!(block_on(map.sum(&[])), Err("no keys".to_owned()));
+            assert_eq!(block_on(map.sum(&["x", "a"])), Err("missing x".to_owned()));
+            assert_eq!(block_on(map.get("b")), Some(2));
+
+            assert_eq!(block_on(map.parse("12")), 12);
+            assert_eq!(block_on(map.parse("-")), 0);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn try_cancelled_mid_loop() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        async fn process(items: &[u32], token: Arc<AtomicBool>) -> Result<u32, u32> {
+            let mut sum = 0;
+            for &item in items {
+                try_cancelled!(token, Err(sum));
+                sum += item;
+                if item == 0 {
+                    token.store(true, Ordering::Release);
+                }
+                super::yield_now().await;
+            }
+            Ok(sum)
+        }
+
+        let token = Arc::new(AtomicBool::new(false));
+        assert_eq!(block_on(process(&[1, 2, 3], token.clone())), Ok(6));
+        assert_eq!(block_on(process(&[1, 2, 0, 3, 4], token.clone())), Err(3));
+        assert!(token.load(Ordering::Acquire));
+        assert_eq!(block_on(process(&[1], token)), Err(0));
+
+        let cancelled = Cell::new(false);
+        let mut seen = Vec::new();
+        block_on(async {
+            for i in 0.. {
+                try_cancelled!(&cancelled);
+                seen.push(i);
+                cancelled.set(i == 2);
+            }
+        });
+        assert_eq!(seen, [0, 1, 2]);
+    }
+}
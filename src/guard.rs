@@ -0,0 +1,178 @@
+//! Guards whose control flow is picked by a const
+//!
+//! `return`, `continue` and `break` can't be abstracted into a function, so
+//! [`guard`] only decides *what* should happen as a [`Flow`], and
+//! [`try_guard!`] performs it. The macro dispatches on the kind as a literal
+//! (`0` to return, `1` to continue, `2` to break), which lets other macros
+//! generate guards whose behavior is chosen by a parameter.
+//!
+//! ```
+//! use try_utils::guard::{guard, Flow, CONTINUE};
+//! use try_utils::try_guard;
+//!
+//! assert_eq!(guard::<CONTINUE, _>(None::<u32>), Flow::Continue);
+//!
+//! let mut out = Vec::new();
+//! for val in [Some(1), None, Some(3)] {
+//!     out.push(try_guard!(1, val));
+//! }
+//! assert_eq!(out, vec![1, 3]);
+//! ```
+//!
+//! [`try_guard!`]: crate::try_guard
+
+use crate::TryAsOption;
+
+/// Return from the current function on failure
+pub const RETURN: u8 = 0;
+/// Continue the current loop on failure
+pub const CONTINUE: u8 = 1;
+/// Break the current loop on failure
+pub const BREAK: u8 = 2;
+
+/// What a guard should do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow<T> {
+    /// The guard succeeded with a value
+    Value(T),
+    /// Return from the current function
+    Return,
+    /// Continue the current loop
+    Continue,
+    /// Break the current loop
+    Break,
+}
+
+/// Returns the value of a `Some` or `Ok` as [`Flow::Value`], otherwise the
+/// control flow for `KIND`.
+///
+/// `KIND` must be one of [`RETURN`], [`CONTINUE`] or [`BREAK`], which is
+/// checked at compile time.
+pub fn guard<const KIND: u8, T: TryAsOption>(v: T) -> Flow<T::Output> {
+    const {
+        assert!(
+            KIND == RETURN || KIND == CONTINUE || KIND == BREAK,
+            "invalid guard kind"
+        )
+    };
+    match v.try_as_option() {
+        Some(v) => Flow::Value(v),
+        None if KIND == RETURN => Flow::Return,
+        None if KIND == CONTINUE => Flow::Continue,
+        None => Flow::Break,
+    }
+}
+
+/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
+/// returns, continues or breaks depending on the given kind literal.
+///
+/// The kind is `0` ([`RETURN`]) to return the given value or `()`, `1`
+/// ([`CONTINUE`]) to continue and `2` ([`BREAK`]) to break, optionally with a
+/// label. See the [`guard`](crate::guard) module.
+///
+/// ```
+/// use try_utils::try_guard;
+///
+/// fn my_func(val: Option<i32>) -> i32 {
+///     let val = try_guard!(0, val, 1234);
+///     val
+/// }
+/// assert_eq!(my_func(None), 1234);
+/// ```
+///
+/// [`RETURN`]: crate::guard::RETURN
+/// [`CONTINUE`]: crate::guard::CONTINUE
+/// [`BREAK`]: crate::guard::BREAK
+#[macro_export]
+macro_rules! try_guard {
+    (0, $e: expr) => {
+        $crate::try_guard!(0, $e, ())
+    };
+
+    (0, $e: expr, $ret: expr) => {
+        match $crate::guard::guard::<{ $crate::guard::RETURN }, _>($e) {
+            $crate::guard::Flow::Value(v) => v,
+            _ => return $ret,
+        }
+    };
+
+    (1, $e: expr $(, $label: lifetime)?) => {
+        match $crate::guard::guard::<{ $crate::guard::CONTINUE }, _>($e) {
+            $crate::guard::Flow::Value(v) => v,
+            _ => continue $($label)?,
+        }
+    };
+
+    (2, $e: expr $(, $label: lifetime)?) => {
+        match $crate::guard::guard::<{ $crate::guard::BREAK }, _>($e) {
+            $crate::guard::Flow::Value(v) => v,
+            _ => break $($label)?,
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guard_kinds() {
+        assert_eq!(guard::<RETURN, _>(Some(1)), Flow::Value(1));
+        assert_eq!(guard::<RETURN, _>(None::<u32>), Flow::Return);
+        assert_eq!(guard::<CONTINUE, _>(Err::<u32, _>(())), Flow::Continue);
+        assert_eq!(guard::<BREAK, _>(None::<u32>), Flow::Break);
+    }
+
+    #[test]
+    fn try_guard_each_kind() {
+        fn ret(val: Option<u32>) -> u32 {
+            try_guard!(0, val, 0) + 1
+        }
+
+        assert_eq!(ret(Some(1)), 2);
+        assert_eq!(ret(None), 0);
+
+        let mut out = Vec::new();
+        for val in [Some(1), None, Some(3)] {
+            out.push(try_guard!(1, val));
+        }
+        assert_eq!(out, vec![1, 3]);
+
+        let mut out = Vec::new();
+        for val in [Ok(1), Err(()), Ok(3)] {
+            out.push(try_guard!(2, val));
+        }
+        assert_eq!(out, vec![1]);
+
+        let mut count = 0;
+        'outer: for _ in 0..3 {
+            count += 1;
+            for val in [Some(1), None] {
+                let _: u32 = try_guard!(1, val, 'outer);
+            }
+        }
+        assert_eq!(count, 3);
+    }
+
+    macro_rules! generated {
+        ($name: ident, $kind: tt) => {
+            fn $name(vals: &[Option<u32>]) -> Vec<u32> {
+                let mut out = Vec::new();
+                for val in vals {
+                    out.push(try_guard!($kind, *val));
+                }
+                out
+            }
+        };
+    }
+
+    generated!(skip_missing, 1);
+    generated!(stop_at_missing, 2);
+
+    #[test]
+    fn try_guard_generated() {
+        let vals = [Some(1), None, Some(3)];
+        assert_eq!(skip_missing(&vals), vec![1, 3]);
+        assert_eq!(stop_at_missing(&vals), vec![1]);
+    }
+}
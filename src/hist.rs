@@ -0,0 +1,89 @@
+#[doc(hidden)]
+pub use metrics as __metrics;
+
+/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
+/// returns from the current function with the given value or `()` if none is
+/// given. In both cases the time elapsed since the given `Instant` is recorded
+/// in the named histogram.
+///
+/// Samples are tagged with an `outcome` label of `success` or `failure`.
+///
+/// ```
+/// use std::time::Instant;
+/// use try_utils::try_return_hist;
+///
+/// fn my_func(val: Option<i32>) -> i32 {
+///     let start = Instant::now();
+///     let val = try_return_hist!(val, "latency", start, 1234);
+///     val
+/// }
+/// assert_eq!(my_func(Some(10)), 10);
+/// assert_eq!(my_func(None), 1234);
+/// ```
+#[macro_export]
+macro_rules! try_return_hist {
+    ($e: expr, $name: expr, $start: expr) => {
+        $crate::try_return_hist!($e, $name, $start, ())
+    };
+
+    ($e: expr, $name: expr, $start: expr, $ret: expr) => {{
+        use $crate::TryAsOption;
+        let start: ::std::time::Instant = $start;
+        match $e.try_as_option() {
+            Some(v) => {
+                $crate::__metrics::histogram!($name, "outcome" => "success")
+                    .record(start.elapsed());
+                v
+            }
+            None => {
+                $crate::__metrics::histogram!($name, "outcome" => "failure")
+                    .record(start.elapsed());
+                return $ret;
+            }
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    fn guarded(val: Option<i32>) -> i32 {
+        try_return_hist!(val, "latency", Instant::now(), -1)
+    }
+
+    #[test]
+    fn try_return_hist_records() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        metrics::with_local_recorder(&recorder, || {
+            assert_eq!(guarded(Some(10)), 10);
+            assert_eq!(guarded(None), -1);
+            assert_eq!(guarded(None), -1);
+        });
+
+        let mut samples = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| {
+                let (_, key) = key.into_parts();
+                assert_eq!(key.name(), "latency");
+                let outcome = key.labels().next().unwrap().value().to_owned();
+                match value {
+                    DebugValue::Histogram(values) => (outcome, values.len()),
+                    _ => panic!("expected a histogram"),
+                }
+            })
+            .collect::<Vec<_>>();
+        samples.sort();
+
+        assert_eq!(
+            samples,
+            vec![("failure".to_owned(), 2), ("success".to_owned(), 1)]
+        );
+    }
+}
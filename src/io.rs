@@ -0,0 +1,309 @@
+/// Returns the number of bytes read if a read returned `Ok(n)` with `n > 0`,
+/// otherwise returns from the current function with the given value or `()`
+/// if none is given when the reader reached EOF.
+///
+/// Errors are propagated with `?`, so the current function must return a
+/// `Result` whose error can be converted from `io::Error`. See
+/// [`try_read_continue!`] and [`try_read_break!`] for use in read loops.
+///
+/// ```
+/// use std::io::{self, Read};
+/// use try_utils::try_read;
+///
+/// fn first_byte(mut reader: impl Read) -> io::Result<Option<u8>> {
+///     let mut buf = [0; 1];
+///     try_read!(reader.read(&mut buf), Ok(None));
+///     Ok(Some(buf[0]))
+/// }
+/// assert_eq!(first_byte(&b"abc"[..]).unwrap(), Some(b'a'));
+/// assert_eq!(first_byte(&b""[..]).unwrap(), None);
+/// ```
+///
+/// [`try_read_continue!`]: crate::try_read_continue
+/// [`try_read_break!`]: crate::try_read_break
+#[macro_export]
+macro_rules! try_read {
+    ($e: expr) => {
+        $crate::try_read!($e, ())
+    };
+
+    ($e: expr, $ret: expr) => {
+        match $e? {
+            0 => return $ret,
+            n => n,
+        }
+    };
+}
+
+/// Returns the number of bytes read if a read returned `Ok(n)` with `n > 0`,
+/// otherwise continues the current loop when the reader reached EOF.
+///
+/// Errors are propagated with `?`. An optional label can be given to continue
+/// a loop with a label.
+///
+/// ```
+/// use std::io::{self, Read};
+/// use try_utils::try_read_continue;
+///
+/// fn total(readers: Vec<&[u8]>) -> io::Result<usize> {
+///     let mut total = 0;
+///     let mut buf = [0; 2];
+///     for mut reader in readers {
+///         total += try_read_continue!(reader.read(&mut buf));
+///     }
+///     Ok(total)
+/// }
+/// assert_eq!(total(vec![b"a", b"", b"bc"]).unwrap(), 3);
+/// ```
+#[macro_export]
+macro_rules! try_read_continue {
+    ($e: expr) => {
+        match $e? {
+            0 => continue,
+            n => n,
+        }
+    };
+
+    ($e: expr, $label: lifetime) => {
+        match $e? {
+            0 => continue $label,
+            n => n,
+        }
+    };
+}
+
+/// Returns the number of bytes read if a read returned `Ok(n)` with `n > 0`,
+/// otherwise breaks the current loop when the reader reached EOF.
+///
+/// Errors are propagated with `?`. An optional label can be given to break a
+/// loop with a label.
+///
+/// ```
+/// use std::io::{self, Read};
+/// use try_utils::try_read_break;
+///
+/// fn read_all(mut reader: impl Read) -> io::Result<Vec<u8>> {
+///     let mut out = Vec::new();
+///     let mut buf = [0; 2];
+///     loop {
+///         let n = try_read_break!(reader.read(&mut buf));
+///         out.extend_from_slice(&buf[..n]);
+///     }
+///     Ok(out)
+/// }
+/// assert_eq!(read_all(&b"hello"[..]).unwrap(), b"hello");
+/// ```
+#[macro_export]
+macro_rules! try_read_break {
+    ($e: expr) => {
+        match $e? {
+            0 => break,
+            n => n,
+        }
+    };
+
+    ($e: expr, $label: lifetime) => {
+        match $e? {
+            0 => break $label,
+            n => n,
+        }
+    };
+}
+
+/// Calls a closure until it returns something other than an `Err` of kind
+/// `ErrorKind::Interrupted`, and returns the value if it returned `Ok`.
+///
+/// Any other error is propagated like `?`, or if a value is given returns
+/// from the current function with that value instead.
+///
+/// ```
+/// use std::io::{self, ErrorKind};
+/// use try_utils::try_eintr;
+///
+/// fn read_once(mut attempts: u32) -> io::Result<u32> {
+///     let val = try_eintr!(|| {
+///         attempts += 1;
+///         if attempts < 3 {
+///             Err(io::Error::from(ErrorKind::Interrupted))
+///         } else {
+///             Ok(attempts)
+///         }
+///     });
+///     Ok(val)
+/// }
+/// assert_eq!(read_once(0).unwrap(), 3);
+/// ```
+#[macro_export]
+macro_rules! try_eintr {
+    (@retry $f: expr, $e: ident => $on_err: expr) => {{
+        let mut f = $f;
+        loop {
+            match f() {
+                ::core::result::Result::Ok(v) => break v,
+                ::core::result::Result::Err(e)
+                    if ::std::io::Error::kind(&e) == ::std::io::ErrorKind::Interrupted => {}
+                ::core::result::Result::Err($e) => $on_err,
+            }
+        }
+    }};
+
+    ($f: expr) => {
+        $crate::try_eintr!(@retry $f, e => {
+            return ::core::result::Result::Err(::core::convert::From::from(e))
+        })
+    };
+
+    ($f: expr, $ret: expr) => {
+        $crate::try_eintr!(@retry $f, _e => return $ret)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+
+    struct Chunks {
+        chunks: Vec<&'static [u8]>,
+        fail: bool,
+    }
+
+    impl Read for Chunks {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.chunks.is_empty() {
+                if self.fail {
+                    return Err(io::Error::other("broken"));
+                }
+                return Ok(0);
+            }
+            let chunk = self.chunks.remove(0);
+            buf[..chunk.len()].copy_from_slice(chunk);
+            Ok(chunk.len())
+        }
+    }
+
+    #[test]
+    fn try_read_eof() {
+        fn read_chunks(reader: &mut Chunks) -> io::Result<Vec<usize>> {
+            let mut sizes = Vec::new();
+            let mut buf = [0; 8];
+            loop {
+                sizes.push(try_read!(reader.read(&mut buf), Ok(sizes)));
+            }
+        }
+
+        let mut reader = Chunks {
+            chunks: vec![b"ab", b"cde"],
+            fail: false,
+        };
+        assert_eq!(read_chunks(&mut reader).unwrap(), vec![2, 3]);
+
+        let mut reader = Chunks {
+            chunks: vec![b"ab"],
+            fail: true,
+        };
+        assert_eq!(read_chunks(&mut reader).unwrap_err().to_string(), "broken");
+    }
+
+    #[test]
+    #[allow(clippy::never_loop)]
+    fn try_read_loops() {
+        fn read_until_eof(reader: &mut Chunks) -> io::Result<Vec<u8>> {
+            let mut out = Vec::new();
+            let mut buf = [0; 8];
+            'outer: loop {
+                loop {
+                    let n = try_read_break!(reader.read(&mut buf), 'outer);
+                    out.extend_from_slice(&buf[..n]);
+                }
+            }
+            Ok(out)
+        }
+
+        fn count_nonempty(readers: &mut [Chunks]) -> io::Result<usize> {
+            let mut count = 0;
+            let mut buf = [0; 8];
+            for reader in readers {
+                try_read_continue!(reader.read(&mut buf));
+                count += 1;
+            }
+            Ok(count)
+        }
+
+        let mut reader = Chunks {
+            chunks: vec![b"ab", b"cd"],
+            fail: false,
+        };
+        assert_eq!(read_until_eof(&mut reader).unwrap(), b"abcd");
+
+        let mut readers = [
+            Chunks {
+                chunks: vec![b"a"],
+                fail: false,
+            },
+            Chunks {
+                chunks: vec![],
+                fail: false,
+            },
+            Chunks {
+                chunks: vec![b"b"],
+                fail: false,
+            },
+        ];
+        assert_eq!(count_nonempty(&mut readers).unwrap(), 2);
+    }
+
+    #[test]
+    fn try_read_thiserror_from() {
+        #[derive(Debug, thiserror::Error)]
+        enum Error {
+            #[error("io: {0}")]
+            Io(#[from] io::Error),
+        }
+
+        fn first(reader: &mut Chunks) -> Result<Option<usize>, Error> {
+            let mut buf = [0; 8];
+            let n = try_read!(reader.read(&mut buf), Ok(None));
+            Ok(Some(n))
+        }
+
+        let mut reader = Chunks {
+            chunks: vec![],
+            fail: true,
+        };
+        assert_eq!(first(&mut reader).unwrap_err().to_string(), "io: broken");
+    }
+
+    fn interrupted_twice() -> impl FnMut() -> io::Result<u32> {
+        let mut calls = 0;
+        move || {
+            calls += 1;
+            match calls {
+                1 | 2 => Err(io::Error::from(io::ErrorKind::Interrupted)),
+                n => Ok(n),
+            }
+        }
+    }
+
+    #[test]
+    fn try_eintr_retries() {
+        fn propagated(f: impl FnMut() -> io::Result<u32>) -> io::Result<u32> {
+            Ok(try_eintr!(f))
+        }
+
+        fn fallback(f: impl FnMut() -> io::Result<u32>) -> u32 {
+            try_eintr!(f, 0)
+        }
+
+        assert_eq!(propagated(interrupted_twice()).unwrap(), 3);
+        assert_eq!(fallback(interrupted_twice()), 3);
+
+        let mut calls = 0;
+        let mut broken = || {
+            calls += 1;
+            Err(io::Error::other("broken"))
+        };
+        assert_eq!(propagated(&mut broken).unwrap_err().to_string(), "broken");
+        assert_eq!(fallback(&mut broken), 0);
+        assert_eq!(calls, 2);
+    }
+}
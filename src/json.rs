@@ -0,0 +1,39 @@
+#[doc(hidden)]
+pub use serde_json as __serde_json;
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use crate::try_return;
+
+    #[test]
+    fn try_return_json_err() {
+        fn parse(val: Result<u32, ()>, field: &str) -> Result<u32, Value> {
+            let val = try_return!(val, json_err: {"code": 400, "field": field});
+            Ok(val)
+        }
+
+        assert_eq!(parse(Ok(10), "id"), Ok(10));
+        let err = parse(Err(()), "id").unwrap_err();
+        assert_eq!(err["code"], 400);
+        assert_eq!(err["field"], "id");
+    }
+
+    #[test]
+    fn try_return_json_err_status() {
+        fn handler(val: Option<u32>, built: &mut bool) -> (u16, Value) {
+            let val = try_return!(val, json_err: {"msg": "bad"}, |err| {
+                *built = true;
+                (400, err)
+            });
+            (200, json!({ "val": val }))
+        }
+
+        let mut built = false;
+        assert_eq!(handler(Some(10), &mut built), (200, json!({"val": 10})));
+        assert!(!built);
+        assert_eq!(handler(None, &mut built), (400, json!({"msg": "bad"})));
+        assert!(built);
+    }
+}
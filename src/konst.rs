@@ -0,0 +1,90 @@
+//! `const fn` guard helpers
+//!
+//! The macros in this crate dispatch through [`TryAsOption`], which can't be
+//! called in const contexts. These functions mirror the macros for `Option`,
+//! `Result` and `bool` so guards can be evaluated at compile time.
+//!
+//! ```
+//! use try_utils::konst;
+//!
+//! const PORT: u16 = konst::unwrap_or(None, 8080);
+//! assert_eq!(PORT, 8080);
+//! ```
+//!
+//! [`TryAsOption`]: crate::TryAsOption
+
+/// Returns the value of an option if it is `Some`, otherwise returns the
+/// default.
+pub const fn unwrap_or<T: Copy>(opt: Option<T>, default: T) -> T {
+    match opt {
+        Some(v) => v,
+        None => default,
+    }
+}
+
+/// Returns the value of a result if it is `Ok`, otherwise returns the
+/// default.
+pub const fn result_unwrap_or<T: Copy, E: Copy>(res: Result<T, E>, default: T) -> T {
+    match res {
+        Ok(v) => v,
+        Err(_) => default,
+    }
+}
+
+/// Converts a result to an option, discarding the error.
+pub const fn ok<T: Copy, E: Copy>(res: Result<T, E>) -> Option<T> {
+    match res {
+        Ok(v) => Some(v),
+        Err(_) => None,
+    }
+}
+
+/// Returns `Some(v)` if the condition holds, otherwise `None`.
+pub const fn then_some<T: Copy>(cond: bool, v: T) -> Option<T> {
+    if cond {
+        Some(v)
+    } else {
+        None
+    }
+}
+
+/// Returns the value of an option if it is `Some`, otherwise panics with the
+/// given message. In a const context the panic is a compile error.
+pub const fn expect<T: Copy>(opt: Option<T>, msg: &str) -> T {
+    match opt {
+        Some(v) => v,
+        None => panic!("{}", msg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn konst_option() {
+        assert_eq!(const { unwrap_or(Some(10), 0) }, 10);
+        assert_eq!(const { unwrap_or(None, 0) }, 0);
+        assert_eq!(const { expect(Some(10), "missing") }, 10);
+    }
+
+    #[test]
+    fn konst_result() {
+        assert_eq!(const { result_unwrap_or(Ok::<_, u8>(10), 0) }, 10);
+        assert_eq!(const { result_unwrap_or(Err(1u8), 0) }, 0);
+        assert_eq!(const { ok(Ok::<_, u8>(10)) }, Some(10));
+        assert_eq!(const { ok::<u32, _>(Err(1u8)) }, None);
+    }
+
+    #[test]
+    fn konst_bool() {
+        assert_eq!(const { then_some(true, 10) }, Some(10));
+        assert_eq!(const { then_some(false, 10) }, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "missing")]
+    fn konst_expect_none() {
+        expect::<u32>(None, "missing");
+    }
+}
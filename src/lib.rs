@@ -68,6 +68,140 @@
 //!     assert_eq!(val, 10);
 //! }
 //! ```
+//!
+//! ## `no_std`
+//!
+//! The `std` feature is enabled by default. Without it the crate is
+//! `no_std`, and the core macros like `try_return!`, `try_continue!` and
+//! `try_break!` only need `core`. Macros that allocate, such as those boxing
+//! errors or formatting messages, are available with the `alloc` feature.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+#![cfg_attr(
+    all(test, feature = "nightly"),
+    feature(coroutines, coroutine_trait, stmt_expr_attributes)
+)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "no-unwind")]
+mod abort;
+#[cfg(feature = "nightly")]
+mod coroutine;
+mod either;
+#[cfg(feature = "alloc")]
+mod ensure;
+#[cfg(feature = "std")]
+mod env;
+#[cfg(feature = "serde")]
+mod envelope;
+#[cfg(feature = "alloc")]
+mod error;
+#[cfg(feature = "std")]
+mod exit;
+#[cfg(feature = "async")]
+mod future;
+pub mod guard;
+#[cfg(feature = "metrics")]
+mod hist;
+#[cfg(feature = "std")]
+mod io;
+#[cfg(feature = "serde_json")]
+mod json;
+pub mod konst;
+mod located;
+#[cfg(feature = "log")]
+mod logging;
+mod num;
+#[cfg(feature = "nom")]
+mod parser;
+mod push;
+#[cfg(feature = "std")]
+mod record;
+#[cfg(feature = "eyre")]
+mod report;
+#[cfg(feature = "std")]
+mod ringlog;
+mod slice;
+mod stats;
+#[cfg(feature = "std")]
+mod sync;
+mod text;
+#[cfg(feature = "timing")]
+mod timing;
+#[cfg(feature = "tracing")]
+mod trace;
+mod variant;
+#[cfg(feature = "std")]
+mod window;
+
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub extern crate alloc as __alloc;
+#[cfg(feature = "no-unwind")]
+#[doc(hidden)]
+pub use abort::__abort;
+pub use either::Either;
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use env::__parse_bool;
+#[cfg(feature = "serde")]
+pub use envelope::GuardErrorEnvelope;
+#[cfg(feature = "alloc")]
+pub use error::{ContextStack, DisplayError, UserError};
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use exit::__termination;
+#[cfg(feature = "std")]
+pub use exit::ExitWith;
+#[cfg(feature = "async")]
+pub use future::{yield_now, IsCancelled, YieldNow};
+#[cfg(feature = "metrics")]
+#[doc(hidden)]
+pub use hist::__metrics;
+#[cfg(feature = "serde_json")]
+#[doc(hidden)]
+pub use json::__serde_json;
+pub use located::GuardError;
+#[cfg(feature = "log")]
+#[doc(hidden)]
+pub use logging::{__RateLimit, __log};
+#[cfg(feature = "nom")]
+#[doc(hidden)]
+pub use parser::__nom;
+pub use push::TryPush;
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use record::{__debug_dump, __stderr_json};
+#[cfg(feature = "eyre")]
+#[doc(hidden)]
+pub use report::__WrapEyre;
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use ringlog::__ringlog_push;
+#[cfg(feature = "std")]
+pub use ringlog::{clear_failures, recent_failures, Failure, RINGLOG_CAPACITY};
+#[doc(hidden)]
+pub use slice::__window;
+#[doc(hidden)]
+pub use stats::__Tally;
+pub use stats::Stats;
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use sync::{__lock_recover, __read_released, __unlocked};
+#[doc(hidden)]
+pub use text::{__longest_prefix, __parse_pos, __utf8_prefix};
+#[cfg(feature = "timing")]
+#[doc(hidden)]
+pub use timing::__TimingFrame;
+#[cfg(feature = "timing")]
+pub use timing::{clear_timing_tree, timing_tree, TimingNode};
+#[cfg(feature = "tracing")]
+#[doc(hidden)]
+pub use trace::{__TimedSpan, __tracing};
+pub use variant::VariantName;
+#[cfg(feature = "std")]
+pub use window::FailureWindow;
 
 /// A trait for converting a type to an option to use in try_utils macros
 pub trait TryAsOption {
@@ -90,6 +224,76 @@ impl<T, E> TryAsOption for Result<T, E> {
     }
 }
 
+/// A trait for converting a type to a result to use in try_utils macros that
+/// need the reason a value is missing
+///
+/// `None` is converted to `Err(())`.
+pub trait TryAsResult {
+    type Output;
+    type Error;
+    /// Converts this type to a result
+    fn try_as_result(self) -> Result<Self::Output, Self::Error>;
+}
+
+impl<T> TryAsResult for Option<T> {
+    type Output = T;
+    type Error = ();
+    fn try_as_result(self) -> Result<Self::Output, Self::Error> {
+        self.ok_or(())
+    }
+}
+
+impl<T, E> TryAsResult for Result<T, E> {
+    type Output = T;
+    type Error = E;
+    fn try_as_result(self) -> Result<Self::Output, Self::Error> {
+        self
+    }
+}
+
+/// An object safe version of [`TryAsOption`] which only checks whether a
+/// value is present, for checking guards in a list built at runtime
+///
+/// ```
+/// use try_utils::TryAsOptionErased;
+///
+/// let guards: Vec<Box<dyn TryAsOptionErased>> = vec![
+///     Box::new(Some(1)),
+///     Box::new(Err::<(), _>("bad")),
+/// ];
+/// assert!(guards[0].is_present());
+/// assert!(!guards[1].is_present());
+/// ```
+pub trait TryAsOptionErased {
+    /// Returns `true` if this is `Some` or `Ok`
+    fn is_present(&self) -> bool;
+}
+
+impl<T> TryAsOptionErased for Option<T> {
+    fn is_present(&self) -> bool {
+        self.is_some()
+    }
+}
+
+impl<T, E> TryAsOptionErased for Result<T, E> {
+    fn is_present(&self) -> bool {
+        self.is_ok()
+    }
+}
+
+impl<T: TryAsOptionErased + ?Sized> TryAsOptionErased for &T {
+    fn is_present(&self) -> bool {
+        (**self).is_present()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: TryAsOptionErased + ?Sized> TryAsOptionErased for alloc::boxed::Box<T> {
+    fn is_present(&self) -> bool {
+        (**self).is_present()
+    }
+}
+
 /// Returns the value of an expression if it is `Some` or `Ok`, otherwise
 /// returns from the current function with the given value or `()` if none is
 /// given.
@@ -104,207 +308,2630 @@ impl<T, E> TryAsOption for Result<T, E> {
 /// assert_eq!(my_func(Some(10)), 10);
 /// assert_eq!(my_func(None), 1234);
 /// ```
-#[macro_export]
-macro_rules! try_return {
-    ($e: expr) => {
-        try_return!($e, ());
-    };
-
-    ($e: expr, $ret: expr) => {{
-        use $crate::TryAsOption;
-        match $e.try_as_option() {
-            Some(v) => v,
-            None => return $ret,
-        }
-    }};
-}
-
-/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
-/// continues the current loop.
 ///
-/// An optional label can be given to continue a loop with a label.
+/// The return value is evaluated in the current function only if the guard
+/// fails, so a `?` in it propagates from the current function like anywhere
+/// else.
 ///
 /// ```
-/// use try_utils::try_continue;
+/// use try_utils::try_return;
 ///
-/// 'label: for _ in 0..10 {
-///     for _ in 0..10 {
-///         let _: u32 = try_continue!(None, 'label);
-///         panic!();
+/// fn port(val: Option<u16>, default: &str) -> Result<u16, std::num::ParseIntError> {
+///     let val = try_return!(val, Ok(default.parse()?));
+///     Ok(val)
+/// }
+/// assert_eq!(port(None, "80"), Ok(80));
+/// assert!(port(None, "http").is_err());
+/// ```
+///
+/// The failure value can instead be an error whose message is written by a
+/// closure with `display:`. The closure is only run if the error is displayed
+/// and the error is converted into the function's error type with `Into`.
+///
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// use try_utils::{try_return, DisplayError};
+///
+/// fn my_func(val: Option<i32>, x: u32) -> Result<i32, DisplayError> {
+///     let val = try_return!(val, display: |f| write!(f, "failed: {}", x));
+///     Ok(val)
+/// }
+/// assert_eq!(my_func(Some(10), 1).unwrap(), 10);
+/// assert_eq!(my_func(None, 1).unwrap_err().to_string(), "failed: 1");
+/// # }
+/// ```
+///
+/// A message meant for end users can be returned with `user_msg:`, which
+/// returns a [`UserError`] converted into the function's error type.
+///
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// use try_utils::{try_return, UserError};
+///
+/// fn my_func(val: Option<i32>) -> Result<i32, UserError> {
+///     let val = try_return!(val, user_msg: "Please provide a valid value");
+///     Ok(val)
+/// }
+/// assert_eq!(my_func(Some(10)).unwrap(), 10);
+/// assert_eq!(my_func(None).unwrap_err().message(), "Please provide a valid value");
+/// # }
+/// ```
+///
+/// With `ringlog:` the failure message and the location of the guard are
+/// recorded in a bounded ring log before returning, which can be read back
+/// with [`recent_failures`].
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use try_utils::try_return;
+///
+/// fn my_func(val: Option<i32>) -> i32 {
+///     let val = try_return!(val, ringlog: "val was missing", 1234);
+///     val
+/// }
+/// assert_eq!(my_func(None), 1234);
+/// assert_eq!(try_utils::recent_failures().last().unwrap().message, "val was missing");
+/// # }
+/// ```
+///
+/// With `stderr_json:` a single line JSON record with the event, the guarded
+/// expression and the location of the guard is written to stderr before
+/// returning. The record is only built if the guard fails.
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use try_utils::try_return;
+///
+/// fn my_func(val: Option<i32>) -> i32 {
+///     // {"event":"missing_val","guard":"val","file":"src/main.rs","line":4}
+///     let val = try_return!(val, stderr_json: "missing_val", 1234);
+///     val
+/// }
+/// assert_eq!(my_func(None), 1234);
+/// # }
+/// ```
+///
+/// With the `serde_json` feature, `json_err:` builds a `serde_json::Value`
+/// using the `json!` syntax on the failure path only. The value is returned as
+/// `Err(value.into())`, or passed to a closure building the return value if
+/// one is given.
+///
+/// ```
+/// # #[cfg(feature = "serde_json")] {
+/// use serde_json::{json, Value};
+/// use try_utils::try_return;
+///
+/// fn handler(val: Option<i32>) -> Result<i32, Value> {
+///     let val = try_return!(val, json_err: {"code": 400, "msg": "bad"});
+///     Ok(val)
+/// }
+/// assert_eq!(handler(None), Err(json!({"code": 400, "msg": "bad"})));
+///
+/// fn status(val: Option<i32>) -> (u16, Value) {
+///     let val = try_return!(val, json_err: {"msg": "bad"}, |err| (400, err));
+///     (200, json!(val))
+/// }
+/// assert_eq!(status(Some(10)), (200, json!(10)));
+/// assert_eq!(status(None), (400, json!({"msg": "bad"})));
+/// # }
+/// ```
+///
+/// With the `serde` feature, `envelope: (code, msg)` builds a
+/// [`GuardErrorEnvelope`] on the failure path. It is returned as
+/// `Err(envelope.into())`, or passed to a closure building the return value
+/// if one is given.
+///
+/// ```
+/// # #[cfg(feature = "serde")] {
+/// use try_utils::{try_return, GuardErrorEnvelope};
+///
+/// fn find(id: Option<u32>) -> Result<u32, GuardErrorEnvelope> {
+///     let id = try_return!(id, envelope: (404, "user not found"));
+///     Ok(id)
+/// }
+/// assert_eq!(find(None).unwrap_err().code, 404);
+///
+/// fn status(id: Option<u32>) -> u16 {
+///     let _id = try_return!(id, envelope: (404, "user not found"), |err| err.code);
+///     200
+/// }
+/// assert_eq!(status(None), 404);
+/// # }
+/// ```
+///
+/// In streaming parsers `consumed:` takes a reference to a counter of how much
+/// input has been processed. The guard never changes the counter; the caller
+/// updates it as it goes. On failure the counter's current value is passed to
+/// the given closure, which builds the return value.
+///
+/// ```
+/// use try_utils::try_return;
+///
+/// fn parse(input: &[&str]) -> Result<Vec<u32>, usize> {
+///     let mut consumed = 0;
+///     let mut out = Vec::new();
+///     for item in input {
+///         out.push(try_return!(item.parse().ok(), consumed: &consumed, Err));
+///         consumed += 1;
 ///     }
-///     panic!();
+///     Ok(out)
 /// }
+/// assert_eq!(parse(&["1", "2"]), Ok(vec![1, 2]));
+/// assert_eq!(parse(&["1", "x", "3"]), Err(1));
+/// ```
 ///
-/// for _ in 0..10 {
-///     let val: u32 = try_continue!(Some(10));
-///     assert_eq!(val, 10);
+/// With `push_ctx:` a context message is pushed onto a [`ContextStack`] before
+/// returning, so nested guards build up a trail of what was being done.
+///
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// use try_utils::{try_return, ContextStack};
+///
+/// fn parse_id(raw: &str, stack: &mut ContextStack) -> Option<u32> {
+///     let id = try_return!(raw.parse().ok(), push_ctx: "parsing id", stack, None);
+///     Some(id)
+/// }
+///
+/// fn load_user(raw: &str, stack: &mut ContextStack) -> Result<u32, ()> {
+///     let id = try_return!(parse_id(raw, stack), push_ctx: "loading user", stack, Err(()));
+///     Ok(id)
+/// }
+///
+/// let mut stack = ContextStack::new();
+/// assert_eq!(load_user("x", &mut stack), Err(()));
+/// assert_eq!(stack.to_string(), "loading user: parsing id");
+/// # }
+/// ```
+///
+/// In `main`, `exit:` returns an [`ExitWith`] with the given exit code, which
+/// is converted into the return type so `ExitCode` works too.
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use try_utils::{try_return, ExitWith};
+///
+/// fn run(arg: Option<&str>) -> ExitWith {
+///     let _arg = try_return!(arg, exit: 2);
+///     ExitWith::SUCCESS
+/// }
+/// assert_eq!(run(None), ExitWith(2));
+/// # }
+/// ```
+///
+/// With `memo_fallback:` an expensive fallback is computed once and kept in
+/// the given `OnceLock` (or `OnceCell`), and later failures return a clone of
+/// it.
+///
+/// ```
+/// use std::sync::OnceLock;
+/// use try_utils::try_return;
+///
+/// static DEFAULT: OnceLock<Vec<u32>> = OnceLock::new();
+///
+/// fn values(val: Option<Vec<u32>>) -> Vec<u32> {
+///     let val = try_return!(val, memo_fallback: DEFAULT, || (0..10).collect());
+///     val
+/// }
+/// assert_eq!(values(Some(vec![1])), vec![1]);
+/// assert_eq!(values(None).len(), 10);
+/// ```
+///
+/// With the `log` feature, `target:` and `level:` log the guarded expression
+/// to the given log target at the given level before returning.
+///
+/// ```
+/// # #[cfg(feature = "log")] {
+/// use log::Level;
+/// use try_utils::try_return;
+///
+/// fn my_func(val: Option<i32>) -> i32 {
+///     let val = try_return!(val, target: "myapp::db", level: Level::Warn, 1234);
+///     val
+/// }
+/// assert_eq!(my_func(None), 1234);
+/// # }
+/// ```
+///
+/// `log_and_map:` takes a level and a closure, logs the original error at
+/// that level and returns `Err` with the error mapped by the closure.
+///
+/// ```
+/// # #[cfg(feature = "log")] {
+/// use log::Level;
+/// use try_utils::try_return;
+///
+/// fn parse(s: &str) -> Result<u32, String> {
+///     let val = try_return!(s.parse::<u32>(), log_and_map: (Level::Error, |e| e.to_string()));
+///     Ok(val)
+/// }
+/// assert_eq!(parse("12"), Ok(12));
+/// assert!(parse("x").is_err());
+/// # }
+/// ```
+///
+/// `rate_limited_log:` takes an interval and a message, and logs the message
+/// as a warning at most once per interval for each call site, so a guard
+/// failing in a tight loop doesn't flood the log.
+///
+/// ```
+/// # #[cfg(feature = "log")] {
+/// use std::time::Duration;
+/// use try_utils::try_return;
+///
+/// fn my_func(val: Option<i32>) -> i32 {
+///     let val = try_return!(val, rate_limited_log: (Duration::from_secs(1), "val was missing"), 0);
+///     val
+/// }
+/// for _ in 0..100 {
+///     assert_eq!(my_func(None), 0); // only warns once
+/// }
+/// # }
+/// ```
+///
+/// For functions returning a `Cow`, `borrowed:` returns `Cow::Borrowed` with
+/// the given reference and `owned:` returns `Cow::Owned` with the given
+/// value, which is only computed if the guard fails.
+///
+/// ```
+/// # #[cfg(feature = "alloc")] {
+/// use std::borrow::Cow;
+/// use try_utils::try_return;
+///
+/// fn name(val: Option<&'static str>) -> Cow<'static, str> {
+///     let val = try_return!(val, borrowed: "anonymous");
+///     Cow::Borrowed(val)
+/// }
+/// assert_eq!(name(None), "anonymous");
+///
+/// fn id(val: Option<u32>, next: u32) -> Cow<'static, str> {
+///     let val = try_return!(val, owned: format!("user-{}", next));
+///     Cow::Owned(val.to_string())
+/// }
+/// assert_eq!(id(None, 3), "user-3");
+/// # }
+/// ```
+///
+/// With `debug_dump:` after the return value, the given context is printed
+/// to stderr with `Debug` before returning in debug builds. Release builds
+/// only return.
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use try_utils::try_return;
+///
+/// fn lookup(ids: &[u32], i: usize) -> u32 {
+///     let id = try_return!(ids.get(i), 0, debug_dump: &(ids, i));
+///     *id
+/// }
+/// assert_eq!(lookup(&[1, 2], 5), 0);
+/// # }
+/// ```
+///
+/// With `window:` the failure is recorded in the given [`FailureWindow`],
+/// counting failures and keeping when the first and last happened.
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// use try_utils::{try_return, FailureWindow};
+///
+/// fn health(val: Option<u32>, window: &mut FailureWindow) -> u32 {
+///     try_return!(val, window: window, 0)
+/// }
+///
+/// let mut window = FailureWindow::new();
+/// health(None, &mut window);
+/// assert_eq!(window.count(), 1);
+/// # }
+/// ```
+///
+/// With `where:` a `Some` or `Ok` value is also checked with the given
+/// predicate, which gets a reference to it like [`Option::filter`], and the
+/// guard fails if it returns `false`. The predicate only runs on a present
+/// value.
+///
+/// ```
+/// use try_utils::try_return;
+///
+/// fn positive(val: Option<i32>) -> i32 {
+///     try_return!(val, where: |&v| v > 0, -1)
 /// }
+/// assert_eq!(positive(Some(3)), 3);
+/// assert_eq!(positive(Some(-3)), -1);
+/// assert_eq!(positive(None), -1);
 /// ```
+///
+/// [`FailureWindow`]: crate::FailureWindow
 #[macro_export]
-macro_rules! try_continue {
-    ($e: expr) => {{
+macro_rules! try_return {
+    ($e: expr) => {
+        $crate::try_return!($e, ());
+    };
+
+    ($e: expr, target: $target: expr, level: $level: expr) => {
+        $crate::try_return!($e, target: $target, level: $level, ())
+    };
+
+    ($e: expr, target: $target: expr, level: $level: expr, $ret: expr) => {{
         use $crate::TryAsOption;
         match $e.try_as_option() {
             Some(v) => v,
-            None => continue,
+            None => {
+                $crate::__log::log!(
+                    target: $target,
+                    $level,
+                    "guard failed: {}",
+                    ::core::stringify!($e)
+                );
+                return $ret;
+            }
         }
     }};
 
-    ($e: expr, $label: lifetime) => {{
+    ($e: expr, log_and_map: ($level: expr, $map: expr $(,)?)) => {{
+        use $crate::TryAsResult;
+        match $e.try_as_result() {
+            Ok(v) => v,
+            Err(e) => {
+                $crate::__log::log!(
+                    $level,
+                    "guard failed: {}: {}",
+                    ::core::stringify!($e),
+                    e
+                );
+                return ::core::result::Result::Err($crate::__call(e, $map));
+            }
+        }
+    }};
+
+    ($e: expr, rate_limited_log: ($interval: expr, $msg: expr $(,)?)) => {
+        $crate::try_return!($e, rate_limited_log: ($interval, $msg), ())
+    };
+
+    ($e: expr, rate_limited_log: ($interval: expr, $msg: expr $(,)?), $ret: expr) => {{
         use $crate::TryAsOption;
         match $e.try_as_option() {
             Some(v) => v,
-            None => continue $label,
+            None => {
+                static LIMIT: $crate::__RateLimit = $crate::__RateLimit::new();
+                if LIMIT.allow($interval) {
+                    $crate::__log::warn!("{}", $msg);
+                }
+                return $ret;
+            }
         }
     }};
-}
 
-/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
-/// breaks the current loop.
-///
-/// An optional label can be given to continue a loop with a label.
-///
-/// ```
-/// use try_utils::try_break;
-///
-/// 'label: for _ in 0..10 {
-///     for _ in 0..10 {
-///         let _: u32 = try_break!(None, 'label);
-///         panic!();
-///     }
-///     panic!();
-/// }
-///
-/// for _ in 0..10 {
-///     let val: u32 = try_break!(Some(10));
-///     assert_eq!(val, 10);
-/// }
-/// ```
-#[macro_export]
-macro_rules! try_break {
-    ($e: expr) => {{
+    ($e: expr, consumed: $consumed: expr, $ret: expr) => {{
         use $crate::TryAsOption;
         match $e.try_as_option() {
             Some(v) => v,
-            None => break,
+            None => {
+                let consumed: usize = *$consumed;
+                return $crate::__call(consumed, $ret);
+            }
         }
     }};
 
-    ($e: expr, $label: lifetime) => {{
+    ($e: expr, push_ctx: $ctx: expr, $stack: expr, $ret: expr) => {{
         use $crate::TryAsOption;
         match $e.try_as_option() {
             Some(v) => v,
-            None => break $label,
+            None => {
+                $crate::ContextStack::push($stack, $ctx);
+                return $ret;
+            }
         }
     }};
-}
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn try_return_none() {
-        fn return_one_option() -> i32 {
-            let _: u32 = try_return!(None, 1);
-            0
+    ($e: expr, exit: $code: expr) => {
+        $crate::try_return!(
+            $e,
+            ::core::convert::Into::into($crate::ExitWith($code))
+        )
+    };
+
+    ($e: expr, memo_fallback: $cell: expr, $init: expr) => {
+        $crate::try_return!(
+            $e,
+            ::core::clone::Clone::clone($cell.get_or_init($init))
+        )
+    };
+
+    ($e: expr, ringlog: $msg: expr) => {
+        $crate::try_return!($e, ringlog: $msg, ())
+    };
+
+    ($e: expr, ringlog: $msg: expr, $ret: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => {
+                $crate::__ringlog_push($msg);
+                return $ret;
+            }
+        }
+    }};
+
+    ($e: expr, where: $pred: expr) => {
+        $crate::try_return!($e, where: $pred, ())
+    };
+
+    ($e: expr, where: $pred: expr, $ret: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) if $crate::__tag(&v, $pred) => v,
+            _ => return $ret,
+        }
+    }};
+
+    ($e: expr, window: $window: expr) => {
+        $crate::try_return!($e, window: $window, ())
+    };
+
+    ($e: expr, window: $window: expr, $ret: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => {
+                $crate::FailureWindow::record($window);
+                return $ret;
+            }
+        }
+    }};
+
+    ($e: expr, stderr_json: $event: expr) => {
+        $crate::try_return!($e, stderr_json: $event, ())
+    };
+
+    ($e: expr, stderr_json: $event: expr, $ret: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => {
+                $crate::__stderr_json($event, ::core::stringify!($e));
+                return $ret;
+            }
+        }
+    }};
+
+    ($e: expr, json_err: $json: tt) => {
+        $crate::try_return!($e, json_err: $json, |err| {
+            ::core::result::Result::Err(::core::convert::Into::into(err))
+        })
+    };
+
+    ($e: expr, json_err: $json: tt, $ret: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            ::core::option::Option::Some(v) => v,
+            ::core::option::Option::None => {
+                return $crate::__call($crate::__serde_json::json!($json), $ret)
+            }
+        }
+    }};
+
+    ($e: expr, envelope: ($code: expr, $msg: expr $(,)?)) => {
+        $crate::try_return!($e, envelope: ($code, $msg), |err| {
+            ::core::result::Result::Err(::core::convert::Into::into(err))
+        })
+    };
+
+    ($e: expr, envelope: ($code: expr, $msg: expr $(,)?), $ret: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            ::core::option::Option::Some(v) => v,
+            ::core::option::Option::None => {
+                return $crate::__call($crate::GuardErrorEnvelope::new($code, $msg), $ret)
+            }
+        }
+    }};
+
+    ($e: expr, user_msg: $msg: expr) => {
+        $crate::try_return!(
+            $e,
+            ::core::result::Result::Err(::core::convert::Into::into(
+                $crate::UserError::new($msg)
+            ))
+        )
+    };
+
+    ($e: expr, display: |$f: ident| $body: expr) => {
+        $crate::try_return!(
+            $e,
+            display: move |$f: &mut ::core::fmt::Formatter<'_>| $body
+        )
+    };
+
+    ($e: expr, display: $display: expr) => {
+        $crate::try_return!(
+            $e,
+            ::core::result::Result::Err(::core::convert::Into::into(
+                $crate::DisplayError::new($display)
+            ))
+        )
+    };
+
+    ($e: expr, borrowed: $borrowed: expr) => {
+        $crate::try_return!($e, $crate::__alloc::borrow::Cow::Borrowed($borrowed))
+    };
+
+    ($e: expr, owned: $owned: expr) => {
+        $crate::try_return!($e, $crate::__alloc::borrow::Cow::Owned($owned))
+    };
+
+    ($e: expr, $ret: expr, debug_dump: $ctx: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => {
+                if ::core::cfg!(debug_assertions) {
+                    $crate::__debug_dump(::core::stringify!($e), $ctx);
+                }
+                return $ret;
+            }
+        }
+    }};
+
+    ($e: expr, $ret: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => return $ret,
+        }
+    }};
+}
+
+/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
+/// continues the current loop.
+///
+/// An optional label can be given to continue a loop with a label.
+///
+/// ```
+/// use try_utils::try_continue;
+///
+/// 'label: for _ in 0..10 {
+///     for _ in 0..10 {
+///         let _: u32 = try_continue!(None, 'label);
+///         panic!();
+///     }
+///     panic!();
+/// }
+///
+/// for _ in 0..10 {
+///     let val: u32 = try_continue!(Some(10));
+///     assert_eq!(val, 10);
+/// }
+/// ```
+///
+/// With `count_by:` and `counters:` the error is classified by the given
+/// closure and the count for its kind in a map, like a `HashMap<Kind, usize>`,
+/// is incremented before continuing. `None` is classified as `()`.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use try_utils::try_continue;
+///
+/// let mut counts = HashMap::new();
+/// for s in ["1", "x", "", "2"] {
+///     let _: u32 = try_continue!(s.parse(), count_by: |e| e.to_string(), counters: &mut counts);
+/// }
+/// assert_eq!(counts.values().sum::<usize>(), 2);
+/// ```
+///
+/// With the `tracing` feature, `iter =` emits a warning event carrying the
+/// loop's iteration index and the given message before continuing.
+///
+/// ```
+/// # #[cfg(feature = "tracing")] {
+/// use try_utils::try_continue;
+///
+/// for (i, val) in [Some(1), None].into_iter().enumerate() {
+///     let val: u32 = try_continue!(val, iter = i, "missing value");
+///     assert_eq!(val, 1);
+/// }
+/// # }
+/// ```
+///
+/// With `monotonic:` and `ord:` the value is compared against the last value
+/// yielded, kept in a `&mut Option<T>`, and the loop continues unless the
+/// comparison is the given [`Ordering`], so items out of order are skipped.
+/// The first value is always yielded.
+///
+/// ```
+/// use try_utils::try_continue;
+///
+/// let mut last = None;
+/// let mut seen = Vec::new();
+/// for val in [1, 3, 2, 5, 5, 4, 8] {
+///     let val = try_continue!(Some(val), monotonic: &mut last, ord: Greater);
+///     seen.push(val);
+/// }
+/// assert_eq!(seen, [1, 3, 5, 8]);
+/// ```
+///
+/// With `stats:` the outcome is counted in the given [`Stats`]: `Some` and
+/// `Ok` as processed, `None` as skipped and `Err` as errored.
+///
+/// ```
+/// use try_utils::{try_continue, Stats};
+///
+/// let mut stats = Stats::default();
+/// for val in [Some(1), None, Some(2)] {
+///     let _: u32 = try_continue!(val, stats: &mut stats);
+/// }
+/// assert_eq!((stats.processed, stats.skipped), (2, 1));
+/// ```
+///
+/// With the `log` feature, `log_dedup:` logs an `Err` as a warning before
+/// continuing unless an error with the same `to_string` is already in the
+/// given set, like a `HashSet<String>`, which it is then added to. Repeated
+/// identical errors in a loop are only logged once.
+///
+/// ```
+/// # #[cfg(feature = "log")] {
+/// use std::collections::HashSet;
+/// use try_utils::try_continue;
+///
+/// let mut seen = HashSet::new();
+/// for s in ["x", "1", "x", "y"] {
+///     let _: u32 = try_continue!(s.parse(), log_dedup: &mut seen);
+/// }
+/// assert_eq!(seen.len(), 1);
+/// # }
+/// ```
+///
+/// With `budget:` and `on_exhausted:` failures are tolerated while the given
+/// `&mut u32` error budget lasts. Each failure takes one from the budget and
+/// continues, and a failure once it is zero runs the given control flow, like
+/// `return ret` or `break`, instead.
+///
+/// ```
+/// use try_utils::try_continue;
+///
+/// fn sum(input: &[&str]) -> Option<u32> {
+///     let mut budget = 2;
+///     let mut sum = 0;
+///     for s in input {
+///         sum += try_continue!(s.parse::<u32>(), budget: &mut budget, on_exhausted: return None);
+///     }
+///     Some(sum)
+/// }
+/// assert_eq!(sum(&["1", "x", "2", "y"]), Some(3));
+/// assert_eq!(sum(&["1", "x", "y", "z"]), None);
+/// ```
+///
+/// [`Ordering`]: core::cmp::Ordering
+/// [`Stats`]: crate::Stats
+#[macro_export]
+macro_rules! try_continue {
+    ($e: expr, budget: $budget: expr, on_exhausted: $action: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => {
+                let budget: &mut u32 = $budget;
+                match budget.checked_sub(1) {
+                    ::core::option::Option::Some(left) => {
+                        *budget = left;
+                        continue;
+                    }
+                    ::core::option::Option::None => $action,
+                }
+            }
+        }
+    }};
+
+    ($e: expr, log_dedup: $seen: expr) => {
+        match $e {
+            ::core::result::Result::Ok(v) => v,
+            ::core::result::Result::Err(e) => {
+                let seen = $seen;
+                let msg = $crate::__alloc::string::ToString::to_string(&e);
+                if !seen.contains(&msg) {
+                    $crate::__log::warn!("guard failed: {}: {}", ::core::stringify!($e), msg);
+                    seen.insert(msg);
+                }
+                continue;
+            }
+        }
+    };
+
+    ($e: expr, stats: $stats: expr) => {
+        match $crate::__Tally::tally($e, $stats) {
+            ::core::option::Option::Some(v) => v,
+            ::core::option::Option::None => continue,
+        }
+    };
+
+    ($e: expr, stats: $stats: expr, $label: lifetime) => {
+        match $crate::__Tally::tally($e, $stats) {
+            ::core::option::Option::Some(v) => v,
+            ::core::option::Option::None => continue $label,
+        }
+    };
+
+    ($e: expr, monotonic: $last: expr, ord: $ord: ident) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) if $crate::__advance_monotonic($last, &v, ::core::cmp::Ordering::$ord) => v,
+            _ => continue,
+        }
+    }};
+
+    ($e: expr, monotonic: $last: expr, ord: $ord: ident, $label: lifetime) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) if $crate::__advance_monotonic($last, &v, ::core::cmp::Ordering::$ord) => v,
+            _ => continue $label,
+        }
+    }};
+
+    ($e: expr, count_by: $classify: expr, counters: $counters: expr) => {{
+        use $crate::TryAsResult;
+        match $e.try_as_result() {
+            Ok(v) => v,
+            Err(e) => {
+                let kind = $crate::__tag(&e, $classify);
+                *$counters.entry(kind).or_insert(0) += 1;
+                continue;
+            }
+        }
+    }};
+
+    ($e: expr, iter = $i: expr, $msg: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => {
+                $crate::__tracing::warn!(iter = $i, guard = ::core::stringify!($e), "{}", $msg);
+                continue;
+            }
+        }
+    }};
+
+    ($e: expr, iter = $i: expr, $msg: expr, $label: lifetime) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => {
+                $crate::__tracing::warn!(iter = $i, guard = ::core::stringify!($e), "{}", $msg);
+                continue $label;
+            }
+        }
+    }};
+
+    ($e: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => continue,
+        }
+    }};
+
+    ($e: expr, $label: lifetime) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => continue $label,
+        }
+    }};
+}
+
+/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
+/// breaks the current loop.
+///
+/// An optional label can be given to continue a loop with a label.
+///
+/// ```
+/// use try_utils::try_break;
+///
+/// 'label: for _ in 0..10 {
+///     for _ in 0..10 {
+///         let _: u32 = try_break!(None, 'label);
+///         panic!();
+///     }
+///     panic!();
+/// }
+///
+/// for _ in 0..10 {
+///     let val: u32 = try_break!(Some(10));
+///     assert_eq!(val, 10);
+/// }
+/// ```
+#[macro_export]
+macro_rules! try_break {
+    ($e: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => break,
+        }
+    }};
+
+    ($e: expr, $label: lifetime) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => break $label,
+        }
+    }};
+}
+
+/// Returns the value of a `Result` whose error type is uninhabited, such as
+/// `Result<T, Infallible>`.
+///
+/// No control flow is emitted for the `Err` arm, so this fails to compile if
+/// the error type can actually be constructed.
+///
+/// ```
+/// use std::convert::Infallible;
+/// use try_utils::try_infallible;
+///
+/// let val: i32 = try_infallible!(Ok::<_, Infallible>(10));
+/// assert_eq!(val, 10);
+/// ```
+///
+/// ```compile_fail
+/// use try_utils::try_infallible;
+///
+/// let val: i32 = try_infallible!(Ok::<_, u32>(10));
+/// ```
+#[macro_export]
+macro_rules! try_infallible {
+    ($e: expr) => {
+        match $e {
+            ::core::result::Result::Ok(v) => v,
+            ::core::result::Result::Err(e) => match e {},
+        }
+    };
+}
+
+#[doc(hidden)]
+pub trait __OkOr<F> {
+    type Output;
+    type Error;
+    fn __ok_or(self, err: impl FnOnce() -> F) -> Result<Self::Output, Self::Error>;
+}
+
+impl<T, F> __OkOr<F> for Option<T> {
+    type Output = T;
+    type Error = F;
+    fn __ok_or(self, err: impl FnOnce() -> F) -> Result<Self::Output, Self::Error> {
+        self.ok_or_else(err)
+    }
+}
+
+impl<T, E, F> __OkOr<F> for Result<T, E> {
+    type Output = T;
+    type Error = E;
+    fn __ok_or(self, _: impl FnOnce() -> F) -> Result<Self::Output, Self::Error> {
+        self
+    }
+}
+
+#[doc(hidden)]
+pub fn __call<T, R>(v: T, f: impl FnOnce(T) -> R) -> R {
+    f(v)
+}
+
+#[doc(hidden)]
+pub fn __call2<T, U, R>(a: T, b: U, f: impl FnOnce(T, U) -> R) -> R {
+    f(a, b)
+}
+
+#[doc(hidden)]
+pub fn __advance_monotonic<T: PartialOrd + Clone>(
+    last: &mut Option<T>,
+    v: &T,
+    ord: core::cmp::Ordering,
+) -> bool {
+    match last {
+        Some(prev) if v.partial_cmp(prev) != Some(ord) => false,
+        _ => {
+            *last = Some(v.clone());
+            true
+        }
+    }
+}
+
+#[doc(hidden)]
+pub fn __tag<T, U>(v: &T, tag: impl FnOnce(&T) -> U) -> U {
+    tag(v)
+}
+
+/// Returns the value of an expression paired with a tag computed from it if it
+/// is `Some` or `Ok`, otherwise returns from the current function with the
+/// given value or `()` if none is given.
+///
+/// The tag closure receives a reference to the value and is only run on
+/// success.
+///
+/// ```
+/// use try_utils::try_tagged;
+///
+/// fn my_func(val: Option<i32>) -> &'static str {
+///     let (val, tag) = try_tagged!(val, tag = |v| *v >= 0, "none");
+///     match tag {
+///         true => "positive",
+///         false => "negative",
+///     }
+/// }
+/// assert_eq!(my_func(Some(10)), "positive");
+/// assert_eq!(my_func(Some(-10)), "negative");
+/// assert_eq!(my_func(None), "none");
+/// ```
+#[macro_export]
+macro_rules! try_tagged {
+    ($e: expr, tag = $tag: expr) => {
+        $crate::try_tagged!($e, tag = $tag, ())
+    };
+
+    ($e: expr, tag = $tag: expr, $ret: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => {
+                let tag = $crate::__tag(&v, $tag);
+                (v, tag)
+            }
+            None => return $ret,
+        }
+    }};
+}
+
+/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
+/// boxes the given value and returns it from the current function.
+///
+/// A function returning `impl Trait` can't return two different types, so
+/// early returning a fallback iterator from one that otherwise returns a
+/// `Map` or `Filter` doesn't compile. Returning `Box<dyn Trait>` instead lets
+/// both paths unify: the fallback is boxed here and the success path boxes its
+/// own value.
+///
+/// ```
+/// use try_utils::try_return_boxed_impl;
+///
+/// fn evens(val: Option<Vec<i32>>) -> Box<dyn Iterator<Item = i32>> {
+///     let val = try_return_boxed_impl!(val, std::iter::empty());
+///     Box::new(val.into_iter().filter(|v| v % 2 == 0))
+/// }
+/// assert_eq!(evens(Some(vec![1, 2, 3, 4])).collect::<Vec<_>>(), vec![2, 4]);
+/// assert_eq!(evens(None).count(), 0);
+/// ```
+#[cfg(feature = "alloc")]
+#[macro_export]
+macro_rules! try_return_boxed_impl {
+    ($e: expr, $ret: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => return $crate::__alloc::boxed::Box::new($ret),
+        }
+    }};
+}
+
+/// Converts a value with `TryFrom`, otherwise returns from the current
+/// function with the given value or `()` if none is given.
+///
+/// This is useful for parsing enum discriminants from integers.
+///
+/// ```
+/// use try_utils::try_from_return;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Op {
+///     Read,
+///     Write,
+/// }
+///
+/// impl TryFrom<u8> for Op {
+///     type Error = ();
+///     fn try_from(raw: u8) -> Result<Self, Self::Error> {
+///         match raw {
+///             0 => Ok(Op::Read),
+///             1 => Ok(Op::Write),
+///             _ => Err(()),
+///         }
+///     }
+/// }
+///
+/// fn decode(raw: u8) -> Option<Op> {
+///     let op = try_from_return!(raw => Op, None);
+///     Some(op)
+/// }
+/// assert_eq!(decode(1), Some(Op::Write));
+/// assert_eq!(decode(7), None);
+/// ```
+#[macro_export]
+macro_rules! try_from_return {
+    ($raw: expr => $ty: ty) => {
+        $crate::try_from_return!($raw => $ty, ())
+    };
+
+    ($raw: expr => $ty: ty, $ret: expr) => {
+        match <$ty as ::core::convert::TryFrom<_>>::try_from($raw) {
+            ::core::result::Result::Ok(v) => v,
+            ::core::result::Result::Err(_) => return $ret,
+        }
+    };
+}
+
+/// Returns the value of an expression if it is `Some` or `Ok` and differs from
+/// the previous value, otherwise continues the current loop.
+///
+/// The previous value is kept in the given `&mut Option<T>`, which is updated
+/// each time a new value is yielded, so runs of equal values only yield once.
+/// An optional label can be given to continue a loop with a label.
+///
+/// ```
+/// use try_utils::try_continue_if_same;
+///
+/// let mut last = None;
+/// let mut out = Vec::new();
+/// for val in [Some(1), Some(1), None, Some(2), Some(1)] {
+///     out.push(try_continue_if_same!(val, last: &mut last));
+/// }
+/// assert_eq!(out, vec![1, 2, 1]);
+/// ```
+#[macro_export]
+macro_rules! try_continue_if_same {
+    ($e: expr, last: $last: expr) => {{
+        use $crate::TryAsOption;
+        let last: &mut ::core::option::Option<_> = $last;
+        match $e.try_as_option() {
+            Some(v) => {
+                if last.as_ref() == Some(&v) {
+                    continue;
+                }
+                *last = Some(::core::clone::Clone::clone(&v));
+                v
+            }
+            None => continue,
+        }
+    }};
+
+    ($e: expr, last: $last: expr, $label: lifetime) => {{
+        use $crate::TryAsOption;
+        let last: &mut ::core::option::Option<_> = $last;
+        match $e.try_as_option() {
+            Some(v) => {
+                if last.as_ref() == Some(&v) {
+                    continue $label;
+                }
+                *last = Some(::core::clone::Clone::clone(&v));
+                v
+            }
+            None => continue $label,
+        }
+    }};
+}
+
+/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
+/// passes the builder to the given closure and returns its result from the
+/// current function.
+///
+/// This lets the fallback inspect how far a builder got before the guard
+/// failed. The builder is only moved on the failure path, so it can still be
+/// used after the guard.
+///
+/// ```
+/// use try_utils::try_build_return;
+///
+/// #[derive(Default)]
+/// struct Request {
+///     url: Option<String>,
+///     port: Option<u16>,
+/// }
+///
+/// fn build(url: Option<&str>, port: Option<u16>) -> Result<Request, String> {
+///     let mut req = Request::default();
+///     req.url = Some(try_build_return!(url, req, |_| Err("no url".to_owned())).to_owned());
+///     req.port = Some(try_build_return!(port, req, |req| {
+///         Err(format!("no port for {}", req.url.unwrap()))
+///     }));
+///     Ok(req)
+/// }
+/// assert_eq!(build(None, None).err().unwrap(), "no url");
+/// assert_eq!(build(Some("a.com"), None).err().unwrap(), "no port for a.com");
+/// assert_eq!(build(Some("a.com"), Some(80)).unwrap().port, Some(80));
+/// ```
+#[macro_export]
+macro_rules! try_build_return {
+    ($e: expr, $builder: expr, $ret: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => return $crate::__call($builder, $ret),
+        }
+    }};
+}
+
+/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
+/// returns `Err` from the current function.
+///
+/// For an `Option` the given error is returned, and for a `Result` its own
+/// error is, both converted with `From` like `?` does. This is
+/// `.ok_or(err)?` for options and `?` for results. The error expression is
+/// only evaluated if an option is `None`, and can be left out for a
+/// `Result`.
+///
+/// Since errors go through `From`, conversions generated by `thiserror`'s
+/// `#[from]` work the same as they do with `?`.
+///
+/// ```
+/// use try_utils::try_ok_or;
+///
+/// fn parse(val: Option<&str>) -> Result<u32, Box<dyn std::error::Error>> {
+///     let val = try_ok_or!(val, "missing value");
+///     let val = try_ok_or!(val.parse::<u32>());
+///     Ok(val)
+/// }
+/// assert_eq!(parse(Some("10")).unwrap(), 10);
+/// assert_eq!(parse(None).unwrap_err().to_string(), "missing value");
+/// assert!(parse(Some("x")).is_err());
+/// ```
+#[macro_export]
+macro_rules! try_ok_or {
+    ($e: expr) => {
+        match $e {
+            ::core::result::Result::Ok(v) => v,
+            ::core::result::Result::Err(e) => {
+                return ::core::result::Result::Err(::core::convert::From::from(e))
+            }
+        }
+    };
+
+    ($e: expr, $err: expr) => {
+        match $crate::__OkOr::__ok_or($e, || $err) {
+            ::core::result::Result::Ok(v) => v,
+            ::core::result::Result::Err(e) => {
+                return ::core::result::Result::Err(::core::convert::From::from(e))
+            }
+        }
+    };
+}
+
+/// Returns the value of a `Result<Option<T>, E>` if it is `Ok(Some(v))`,
+/// otherwise returns from the current function with the given value if it is
+/// `Ok(None)`.
+///
+/// An `Err` is returned converted with `From` like `?` does, or passed to a
+/// closure building the return value if one is given.
+///
+/// ```
+/// use try_utils::try_transpose_return;
+///
+/// fn lookup(row: Result<Option<i32>, String>) -> Result<i32, String> {
+///     let val = try_transpose_return!(row, Ok(0));
+///     Ok(val)
+/// }
+/// assert_eq!(lookup(Ok(Some(10))), Ok(10));
+/// assert_eq!(lookup(Ok(None)), Ok(0));
+/// assert_eq!(lookup(Err("db".to_owned())), Err("db".to_owned()));
+///
+/// fn fallback(row: Result<Option<i32>, String>) -> i32 {
+///     try_transpose_return!(row, 0, |_| -1)
+/// }
+/// assert_eq!(fallback(Err("db".to_owned())), -1);
+/// ```
+#[macro_export]
+macro_rules! try_transpose_return {
+    ($e: expr, $ret: expr) => {
+        $crate::try_transpose_return!($e, $ret, |e| {
+            ::core::result::Result::Err(::core::convert::From::from(e))
+        })
+    };
+
+    ($e: expr, $ret: expr, $err: expr) => {
+        match $e {
+            ::core::result::Result::Ok(::core::option::Option::Some(v)) => v,
+            ::core::result::Result::Ok(::core::option::Option::None) => return $ret,
+            ::core::result::Result::Err(e) => return $crate::__call(e, $err),
+        }
+    };
+}
+
+/// Collects an iterator of `Option`s or `Result`s into a `Vec`, otherwise
+/// returns from the current function with the given value at the first
+/// `None` or `Err`.
+///
+/// Without a value the first error is returned converted with `From`, like
+/// collecting into a `Result<Vec<_>, _>` and using `?`.
+///
+/// ```
+/// use try_utils::try_collect_return;
+///
+/// fn parse_all(input: &[&str]) -> Option<Vec<u32>> {
+///     let vals = try_collect_return!(input.iter().map(|s| s.parse::<u32>()), None);
+///     Some(vals)
+/// }
+/// assert_eq!(parse_all(&["1", "2"]), Some(vec![1, 2]));
+/// assert_eq!(parse_all(&["1", "x"]), None);
+///
+/// fn propagate(input: &[&str]) -> Result<Vec<u32>, std::num::ParseIntError> {
+///     Ok(try_collect_return!(input.iter().map(|s| s.parse::<u32>())))
+/// }
+/// assert!(propagate(&["1", "x"]).is_err());
+/// ```
+#[cfg(feature = "alloc")]
+#[macro_export]
+macro_rules! try_collect_return {
+    (@collect $iter: expr, |$err: pat_param| $fail: expr) => {{
+        use $crate::TryAsResult;
+        let mut out = $crate::__alloc::vec::Vec::new();
+        for item in $iter {
+            match item.try_as_result() {
+                Ok(v) => out.push(v),
+                Err($err) => $fail,
+            }
+        }
+        out
+    }};
+
+    ($iter: expr) => {
+        $crate::try_collect_return!(@collect $iter, |e| {
+            return ::core::result::Result::Err(::core::convert::From::from(e))
+        })
+    };
+
+    ($iter: expr, $ret: expr) => {
+        $crate::try_collect_return!(@collect $iter, |_| return $ret)
+    };
+}
+
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub fn __box_any<E: core::any::Any + Send>(e: E) -> alloc::boxed::Box<dyn core::any::Any + Send> {
+    alloc::boxed::Box::new(e)
+}
+
+/// Returns the value of a `Result` if it is `Ok`, otherwise returns from the
+/// current function with the error boxed as a `Box<dyn Any + Send>`.
+///
+/// Without a value this returns `Err` with the boxed error. Otherwise the
+/// value is a closure called with the boxed error, for example to send it
+/// over a channel shared by tasks failing with different error types. The
+/// error can be recovered with `downcast`, the same as a thread's panic
+/// payload.
+///
+/// ```
+/// use std::any::Any;
+/// use try_utils::try_return_any;
+///
+/// fn parse(s: &str) -> Result<u32, Box<dyn Any + Send>> {
+///     let val = try_return_any!(s.parse::<u32>());
+///     Ok(val)
+/// }
+/// let err = parse("x").unwrap_err();
+/// assert!(err.downcast::<std::num::ParseIntError>().is_ok());
+/// ```
+#[cfg(feature = "alloc")]
+#[macro_export]
+macro_rules! try_return_any {
+    ($e: expr) => {
+        $crate::try_return_any!($e, ::core::result::Result::Err)
+    };
+
+    ($e: expr, $ret: expr) => {
+        match $e {
+            ::core::result::Result::Ok(v) => v,
+            ::core::result::Result::Err(e) => return $crate::__call($crate::__box_any(e), $ret),
+        }
+    };
+}
+
+/// Returns the value of a `Result` if it is `Ok`, otherwise returns from the
+/// current function with `Err` of the error converted with `to_string`.
+///
+/// This is `.map_err(|e| e.to_string())?` for functions returning
+/// `Result<_, String>`. The string is converted with `From`, so other error
+/// types that can be built from a `String` work too.
+///
+/// ```
+/// use try_utils::try_stringify_err;
+///
+/// fn parse(s: &str) -> Result<u32, String> {
+///     let val = try_stringify_err!(s.parse::<u32>());
+///     Ok(val)
+/// }
+/// assert_eq!(parse("12"), Ok(12));
+/// assert_eq!(parse("x"), Err("invalid digit found in string".to_owned()));
+/// ```
+#[cfg(feature = "alloc")]
+#[macro_export]
+macro_rules! try_stringify_err {
+    ($e: expr $(,)?) => {
+        match $e {
+            ::core::result::Result::Ok(v) => v,
+            ::core::result::Result::Err(e) => {
+                return ::core::result::Result::Err(::core::convert::From::from(
+                    $crate::__alloc::string::ToString::to_string(&e),
+                ))
+            }
+        }
+    };
+}
+
+/// Runs every check in a list and does nothing if they all returned `Ok`,
+/// otherwise returns from the current function with the result of a closure
+/// called with a `Vec` of every error.
+///
+/// Unlike other guards this doesn't stop at the first failure, so all
+/// validation errors can be reported at once. The checks run in order and
+/// must all return `Result<_, E>` with the same `E`.
+///
+/// ```
+/// use try_utils::try_validate;
+///
+/// fn check(name: &str, age: u32) -> Result<(), Vec<&'static str>> {
+///     try_validate!(
+///         [
+///             if name.is_empty() { Err("empty name") } else { Ok(()) },
+///             if age > 150 { Err("bad age") } else { Ok(()) },
+///         ],
+///         Err
+///     );
+///     Ok(())
+/// }
+/// assert_eq!(check("a", 1), Ok(()));
+/// assert_eq!(check("", 200), Err(vec!["empty name", "bad age"]));
+/// ```
+#[cfg(feature = "alloc")]
+#[macro_export]
+macro_rules! try_validate {
+    ([$($check: expr),* $(,)?], $ret: expr) => {{
+        let mut errors = $crate::__alloc::vec::Vec::new();
+        $(
+            if let ::core::result::Result::Err(e) = $check {
+                errors.push(e);
+            }
+        )*
+        if !errors.is_empty() {
+            return $crate::__call(errors, $ret);
+        }
+    }};
+}
+
+/// Returns the exclusive reference in an `Option<&mut T>` if it is `Some`,
+/// otherwise returns from the current function with the given value or `()`
+/// if none is given.
+///
+/// This is [`try_return!`] restricted to mutable references, such as those
+/// from `slice::get_mut` or `HashMap::get_mut`, so the reborrow keeps the
+/// lifetime of the original borrow.
+///
+/// ```
+/// use try_utils::try_mut;
+///
+/// fn bump(vals: &mut [i32], i: usize) -> bool {
+///     let val = try_mut!(vals.get_mut(i), false);
+///     *val += 1;
+///     true
+/// }
+///
+/// let mut vals = [1, 2, 3];
+/// assert!(bump(&mut vals, 1));
+/// assert!(!bump(&mut vals, 5));
+/// assert_eq!(vals, [1, 3, 3]);
+/// ```
+#[macro_export]
+macro_rules! try_mut {
+    ($e: expr) => {
+        $crate::try_mut!($e, ())
+    };
+
+    ($e: expr, $ret: expr) => {
+        match $e {
+            ::core::option::Option::Some(v) => {
+                let v: &mut _ = v;
+                v
+            }
+            ::core::option::Option::None => return $ret,
+        }
+    };
+}
+
+/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
+/// evaluates to the given default without affecting control flow.
+///
+/// This is for closures such as sort key functions where `return` and
+/// `continue` aren't useful. The default is only evaluated if the expression
+/// is `None` or `Err`.
+///
+/// ```
+/// use try_utils::try_key;
+///
+/// let mut vals = vec![Some(3), None, Some(1)];
+/// vals.sort_by_key(|val| try_key!(*val, i32::MAX));
+/// assert_eq!(vals, vec![Some(1), Some(3), None]);
+/// ```
+#[macro_export]
+macro_rules! try_key {
+    ($e: expr, $default: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            ::core::option::Option::Some(v) => v,
+            ::core::option::Option::None => $default,
+        }
+    }};
+}
+
+/// Returns a mutable reference to the value for a key in a map, inserting the
+/// result of a closure first if the key is absent.
+///
+/// This never fails or affects control flow. The closure is only run if the
+/// key is absent. Works with any map with an entry API like `HashMap` and
+/// `BTreeMap`.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use try_utils::try_or_insert;
+///
+/// let mut counts = HashMap::new();
+/// for word in ["a", "b", "a"] {
+///     *try_or_insert!(counts, word, || 0) += 1;
+/// }
+/// assert_eq!(counts["a"], 2);
+/// ```
+#[macro_export]
+macro_rules! try_or_insert {
+    ($map: expr, $key: expr, $default: expr $(,)?) => {
+        $map.entry($key).or_insert_with($default)
+    };
+}
+
+/// Returns a reference to the next item of a [`Peekable`] iterator without
+/// consuming it, otherwise returns from the current function with the given
+/// value or `()` if none is given when the iterator is exhausted.
+///
+/// The item is still returned by the next call to `next`, which makes this
+/// useful for lookahead in parsers.
+///
+/// ```
+/// use std::iter::Peekable;
+/// use try_utils::{try_peek, try_return};
+///
+/// fn digits(chars: &mut Peekable<std::str::Chars>) -> u32 {
+///     let mut val = 0;
+///     loop {
+///         let c = try_peek!(chars, val);
+///         let digit = try_return!(c.to_digit(10), val);
+///         chars.next();
+///         val = val * 10 + digit;
+///     }
+/// }
+///
+/// let mut chars = "12+3".chars().peekable();
+/// assert_eq!(digits(&mut chars), 12);
+/// assert_eq!(chars.next(), Some('+'));
+/// ```
+///
+/// [`Peekable`]: core::iter::Peekable
+#[macro_export]
+macro_rules! try_peek {
+    ($iter: expr) => {
+        $crate::try_peek!($iter, ())
+    };
+
+    ($iter: expr, $ret: expr) => {
+        match $iter.peek() {
+            ::core::option::Option::Some(v) => v,
+            ::core::option::Option::None => return $ret,
+        }
+    };
+}
+
+#[doc(hidden)]
+pub fn __all_present<I>(guards: I) -> bool
+where
+    I: IntoIterator,
+    I::Item: TryAsOptionErased,
+{
+    guards.into_iter().all(|guard| guard.is_present())
+}
+
+/// Does nothing if every guard in a list is `Some` or `Ok`, otherwise returns
+/// from the current function with the given value or `()` if none is given.
+///
+/// The list can be anything iterable over [`TryAsOptionErased`] values, like
+/// a `&[&dyn TryAsOptionErased]` or a `&Vec<Box<dyn TryAsOptionErased>>`, so
+/// the guards can be chosen at runtime. Guards are checked in order and
+/// checking stops at the first failure.
+///
+/// ```
+/// use try_utils::{try_all_dyn, TryAsOptionErased};
+///
+/// fn check(name: Option<&str>, age: Result<u32, String>) -> bool {
+///     try_all_dyn!(&[&name as &dyn TryAsOptionErased, &age], false);
+///     true
+/// }
+/// assert!(check(Some("a"), Ok(1)));
+/// assert!(!check(None, Ok(1)));
+/// ```
+#[macro_export]
+macro_rules! try_all_dyn {
+    ($guards: expr) => {
+        $crate::try_all_dyn!($guards, ())
+    };
+
+    ($guards: expr, $ret: expr) => {
+        if !$crate::__all_present($guards) {
+            return $ret;
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn try_return_none() {
+        fn return_one_option() -> i32 {
+            let _: u32 = try_return!(None, 1);
+            0
+        }
+
+        fn return_one_result() -> i32 {
+            let _: u32 = try_return!(Err(10), 1);
+            0
+        }
+
+        assert_eq!(return_one_option(), 1);
+        assert_eq!(return_one_result(), 1);
+    }
+
+    #[test]
+    fn try_return_some() {
+        fn return_zero_option() -> i32 {
+            let val: u32 = try_return!(Some(10), 1);
+            assert_eq!(val, 10);
+            0
+        }
+
+        fn return_zero_result() -> i32 {
+            let val: u32 = try_return!(Ok::<_, u64>(10), 1);
+            assert_eq!(val, 10);
+            0
+        }
+
+        assert_eq!(return_zero_option(), 0);
+        assert_eq!(return_zero_result(), 0);
+    }
+
+    #[test]
+    #[allow(clippy::never_loop)]
+    fn try_continue_none() {
+        let mut count = 0;
+        for _ in 0..10 {
+            count += 1;
+            let _: u32 = try_continue!(None);
+            panic!();
+        }
+        assert_eq!(count, 10);
+
+        let mut count = 0;
+        'outer: for _ in 0..10 {
+            count += 1;
+            loop {
+                let _: u32 = try_continue!(None, 'outer);
+                panic!();
+            }
+        }
+        assert_eq!(count, 10);
+    }
+
+    #[test]
+    fn try_continue_some() {
+        let mut count = 0;
+        for _ in 0..10 {
+            count += 1;
+            let val: u32 = try_continue!(Some(10));
+            assert_eq!(val, 10)
+        }
+        assert_eq!(count, 10);
+
+        let mut count = 0;
+        'outer: for _ in 0..10 {
+            count += 1;
+            for _ in 0..10 {
+                let val: u32 = try_continue!(Some(10), 'outer);
+                assert_eq!(val, 10);
+            }
+        }
+        assert_eq!(count, 10);
+    }
+
+    #[test]
+    #[allow(clippy::never_loop)]
+    fn try_break_none() {
+        loop {
+            let _: u32 = try_break!(None);
+            panic!();
+        }
+
+        'outer: loop {
+            for _ in 0..10 {
+                let _: u32 = try_break!(None, 'outer);
+                panic!();
+            }
+            panic!();
+        }
+    }
+
+    #[test]
+    fn try_break_some() {
+        for _ in 0..10 {
+            let val: u32 = try_break!(Some(10));
+            assert_eq!(val, 10)
+        }
+
+        'outer: for _ in 0..10 {
+            for _ in 0..10 {
+                let val: u32 = try_break!(Some(10), 'outer);
+                assert_eq!(val, 10)
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn try_return_display() {
+        fn parse(val: Option<u32>, name: &'static str) -> Result<u32, crate::DisplayError> {
+            let val = try_return!(val, display: |f| write!(f, "missing {}", name));
+            Ok(val)
+        }
+
+        fn boxed(val: Option<u32>) -> Result<u32, Box<dyn std::error::Error>> {
+            let val = try_return!(val, display: |f| f.write_str("boxed"));
+            Ok(val)
+        }
+
+        assert_eq!(parse(Some(10), "a").unwrap(), 10);
+        assert_eq!(parse(None, "a").unwrap_err().to_string(), "missing a");
+        assert_eq!(boxed(Some(10)).unwrap(), 10);
+        assert_eq!(boxed(None).unwrap_err().to_string(), "boxed");
+    }
+
+    #[test]
+    fn try_infallible_ok() {
+        use std::convert::Infallible;
+
+        fn parse(s: &str) -> String {
+            try_infallible!(s.parse::<String>())
+        }
+
+        let val: u32 = try_infallible!(Ok::<_, Infallible>(10));
+        assert_eq!(val, 10);
+        assert_eq!(parse("abc"), "abc");
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn try_return_user_msg() {
+        fn check(val: Result<u32, ()>) -> Result<u32, crate::UserError> {
+            let val = try_return!(val, user_msg: "Please provide a valid value");
+            Ok(val)
+        }
+
+        fn owned(val: Option<u32>, name: &str) -> Result<u32, String> {
+            let val = try_return!(val, user_msg: format!("{} is required", name));
+            Ok(val)
+        }
+
+        assert_eq!(check(Ok(10)), Ok(10));
+        assert_eq!(
+            check(Err(())).unwrap_err().message(),
+            "Please provide a valid value"
+        );
+        assert_eq!(owned(Some(10), "id"), Ok(10));
+        assert_eq!(owned(None, "id").unwrap_err(), "id is required");
+    }
+
+    #[test]
+    fn try_tagged_classifies() {
+        #[derive(Debug, PartialEq)]
+        enum Size {
+            Small,
+            Large,
+        }
+
+        fn classify(v: &u32) -> Size {
+            if *v < 100 {
+                Size::Small
+            } else {
+                Size::Large
+            }
+        }
+
+        fn tagged(val: Result<u32, ()>) -> Option<(u32, Size)> {
+            Some(try_tagged!(val, tag = classify, None))
+        }
+
+        assert_eq!(tagged(Ok(10)), Some((10, Size::Small)));
+        assert_eq!(tagged(Ok(1000)), Some((1000, Size::Large)));
+        assert_eq!(tagged(Err(())), None);
+    }
+
+    #[test]
+    fn try_tagged_only_on_success() {
+        fn tagged(val: Option<u32>, calls: &mut u32) {
+            let (val, tag) = try_tagged!(
+                val,
+                tag = |v| {
+                    *calls += 1;
+                    *v * 2
+                }
+            );
+            assert_eq!(tag, val * 2);
+        }
+
+        let mut calls = 0;
+        tagged(None, &mut calls);
+        assert_eq!(calls, 0);
+        tagged(Some(10), &mut calls);
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn try_return_boxed_impl_unifies() {
+        fn doubled(val: Result<Vec<i32>, ()>) -> Box<dyn Iterator<Item = i32>> {
+            let val = try_return_boxed_impl!(val, [-1].into_iter());
+            Box::new(val.into_iter().map(|v| v * 2))
+        }
+
+        assert_eq!(
+            doubled(Ok(vec![1, 2, 3])).collect::<Vec<_>>(),
+            vec![2, 4, 6]
+        );
+        assert_eq!(doubled(Err(())).collect::<Vec<_>>(), vec![-1]);
+    }
+
+    #[test]
+    fn try_from_return_discriminant() {
+        #[derive(Debug, PartialEq)]
+        enum Kind {
+            A = 1,
+            B = 2,
+        }
+
+        impl TryFrom<u32> for Kind {
+            type Error = u32;
+            fn try_from(raw: u32) -> Result<Self, Self::Error> {
+                match raw {
+                    1 => Ok(Kind::A),
+                    2 => Ok(Kind::B),
+                    raw => Err(raw),
+                }
+            }
+        }
+
+        fn decode(raw: u32) -> Result<Kind, &'static str> {
+            let kind = try_from_return!(raw => Kind, Err("bad kind"));
+            Ok(kind)
+        }
+
+        fn unit(raw: u32, seen: &mut Vec<Kind>) {
+            seen.push(try_from_return!(raw => Kind));
+        }
+
+        assert_eq!(decode(1), Ok(Kind::A));
+        assert_eq!(decode(2), Ok(Kind::B));
+        assert_eq!(decode(3), Err("bad kind"));
+
+        let mut seen = Vec::new();
+        for raw in [0, 2, 5, 1] {
+            unit(raw, &mut seen);
+        }
+        assert_eq!(seen, vec![Kind::B, Kind::A]);
+    }
+
+    #[test]
+    fn try_continue_if_same_runs() {
+        let input: Vec<Result<&str, ()>> = vec![
+            Ok("a"),
+            Ok("a"),
+            Ok("b"),
+            Err(()),
+            Ok("b"),
+            Ok("c"),
+            Ok("c"),
+            Ok("a"),
+        ];
+
+        let mut last = None;
+        let mut out = Vec::new();
+        for val in input.iter().cloned() {
+            out.push(try_continue_if_same!(val, last: &mut last));
         }
+        assert_eq!(out, vec!["a", "b", "c", "a"]);
+        assert_eq!(last, Some("a"));
 
-        fn return_one_result() -> i32 {
-            let _: u32 = try_return!(Err(10), 1);
-            0
+        let mut last = None;
+        let mut out = Vec::new();
+        'outer: for chunk in [[1, 1], [1, 2], [2, 2]] {
+            for val in chunk {
+                out.push(try_continue_if_same!(Some(val), last: &mut last, 'outer));
+            }
         }
+        assert_eq!(out, vec![1, 2]);
+    }
 
-        assert_eq!(return_one_option(), 1);
-        assert_eq!(return_one_result(), 1);
+    #[test]
+    fn try_build_return_partial() {
+        #[derive(Debug, Default, PartialEq)]
+        struct Config {
+            name: Option<&'static str>,
+            size: Option<u32>,
+        }
+
+        fn build(name: Option<&'static str>, size: Option<u32>) -> Result<Config, Config> {
+            let mut config = Config::default();
+            config.name = Some(try_build_return!(name, config, Err));
+            config.size = Some(try_build_return!(size, config, Err));
+            Ok(config)
+        }
+
+        assert_eq!(build(None, Some(1)), Err(Config::default()));
+        assert_eq!(
+            build(Some("a"), None),
+            Err(Config {
+                name: Some("a"),
+                size: None
+            })
+        );
+        assert_eq!(
+            build(Some("a"), Some(1)),
+            Ok(Config {
+                name: Some("a"),
+                size: Some(1)
+            })
+        );
     }
 
     #[test]
-    fn try_return_some() {
-        fn return_zero_option() -> i32 {
-            let val: u32 = try_return!(Some(10), 1);
-            assert_eq!(val, 10);
-            0
+    fn try_ok_or_carriers() {
+        #[derive(Debug, PartialEq)]
+        enum Error {
+            Missing,
+            Parse,
         }
 
-        fn return_zero_result() -> i32 {
-            let val: u32 = try_return!(Ok::<_, u64>(10), 1);
-            assert_eq!(val, 10);
-            0
+        impl From<std::num::ParseIntError> for Error {
+            fn from(_: std::num::ParseIntError) -> Self {
+                Error::Parse
+            }
         }
 
-        assert_eq!(return_zero_option(), 0);
-        assert_eq!(return_zero_result(), 0);
+        fn parse(val: Option<&str>) -> Result<u32, Error> {
+            let val = try_ok_or!(val, Error::Missing);
+            let val = try_ok_or!(val.parse::<u32>(), Error::Missing);
+            Ok(val)
+        }
+
+        fn lazy(val: Option<u32>, evaluated: &mut bool) -> Result<u32, ()> {
+            let val = try_ok_or!(val, *evaluated = true);
+            Ok(val)
+        }
+
+        assert_eq!(parse(Some("10")), Ok(10));
+        assert_eq!(parse(None), Err(Error::Missing));
+        assert_eq!(parse(Some("x")), Err(Error::Parse));
+
+        let mut evaluated = false;
+        assert_eq!(lazy(Some(1), &mut evaluated), Ok(1));
+        assert!(!evaluated);
+        assert_eq!(lazy(None, &mut evaluated), Err(()));
+        assert!(evaluated);
+
+        fn own_error(val: &str) -> Result<u32, Error> {
+            let val = try_ok_or!(val.parse::<u32>());
+            Ok(val)
+        }
+        assert_eq!(own_error("7"), Ok(7));
+        assert_eq!(own_error("x"), Err(Error::Parse));
     }
 
     #[test]
-    fn try_continue_none() {
-        let mut count = 0;
-        for _ in 0..10 {
-            count += 1;
-            let _: u32 = try_continue!(None);
-            panic!();
+    fn try_return_consumed() {
+        #[derive(Debug, PartialEq)]
+        struct Partial {
+            consumed: usize,
+            total: usize,
         }
-        assert_eq!(count, 10);
 
-        let mut count = 0;
-        'outer: for _ in 0..10 {
-            count += 1;
-            loop {
-                let _: u32 = try_continue!(None, 'outer);
-                panic!();
+        fn sum_digits(input: &str) -> Result<u32, Partial> {
+            let mut consumed = 0;
+            let mut sum = 0;
+            for c in input.chars() {
+                sum += try_return!(c.to_digit(10), consumed: &mut consumed, |consumed| {
+                    Err(Partial {
+                        consumed,
+                        total: input.len(),
+                    })
+                });
+                consumed += 1;
             }
+            Ok(sum)
         }
-        assert_eq!(count, 10);
+
+        assert_eq!(sum_digits("123"), Ok(6));
+        assert_eq!(
+            sum_digits("12x4"),
+            Err(Partial {
+                consumed: 2,
+                total: 4
+            })
+        );
+        assert_eq!(
+            sum_digits("x"),
+            Err(Partial {
+                consumed: 0,
+                total: 1
+            })
+        );
     }
 
     #[test]
-    fn try_continue_some() {
-        let mut count = 0;
+    #[cfg(feature = "alloc")]
+    fn try_ok_or_thiserror_from() {
+        #[derive(Debug, thiserror::Error)]
+        enum Error {
+            #[error("missing field")]
+            Missing(#[from] crate::UserError),
+            #[error("bad number")]
+            Parse(#[from] std::num::ParseIntError),
+        }
+
+        fn parse(val: Option<&str>) -> Result<u32, Error> {
+            let val = try_ok_or!(val, crate::UserError::new("missing"));
+            let val = try_ok_or!(val.parse::<u32>(), crate::UserError::new("unused"));
+            Ok(val)
+        }
+
+        fn user_msg(val: Option<u32>) -> Result<u32, Error> {
+            let val = try_return!(val, user_msg: "missing");
+            Ok(val)
+        }
+
+        assert_eq!(parse(Some("10")).unwrap(), 10);
+        assert!(matches!(parse(None), Err(Error::Missing(e)) if e.message() == "missing"));
+        assert!(matches!(parse(Some("x")), Err(Error::Parse(_))));
+        assert!(matches!(user_msg(None), Err(Error::Missing(_))));
+    }
+
+    #[test]
+    fn try_transpose_return_cases() {
+        #[derive(Debug, PartialEq)]
+        enum Error {
+            NotFound,
+            Db(&'static str),
+        }
+
+        impl From<&'static str> for Error {
+            fn from(e: &'static str) -> Self {
+                Error::Db(e)
+            }
+        }
+
+        fn propagate(row: Result<Option<u32>, &'static str>) -> Result<u32, Error> {
+            let val = try_transpose_return!(row, Err(Error::NotFound));
+            Ok(val)
+        }
+
+        fn fallback(row: Result<Option<u32>, &'static str>) -> u32 {
+            let val = try_transpose_return!(row, 0, |e: &str| e.len() as u32);
+            val * 10
+        }
+
+        assert_eq!(propagate(Ok(Some(1))), Ok(1));
+        assert_eq!(propagate(Ok(None)), Err(Error::NotFound));
+        assert_eq!(propagate(Err("down")), Err(Error::Db("down")));
+
+        assert_eq!(fallback(Ok(Some(1))), 10);
+        assert_eq!(fallback(Ok(None)), 0);
+        assert_eq!(fallback(Err("down")), 4);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn try_return_push_ctx() {
+        use crate::ContextStack;
+
+        fn inner(val: Option<u32>, stack: &mut ContextStack) -> Option<u32> {
+            let val = try_return!(val, push_ctx: "reading value", stack, None);
+            Some(val)
+        }
+
+        fn middle(val: Option<u32>, stack: &mut ContextStack) -> Result<u32, ()> {
+            let val = try_return!(inner(val, stack), push_ctx: "parsing row", stack, Err(()));
+            Ok(val)
+        }
+
+        fn outer(val: Option<u32>, stack: &mut ContextStack) -> Result<u32, ()> {
+            let ctx = format!("loading {:?}", val);
+            let val = try_return!(middle(val, stack), push_ctx: ctx, stack, Err(()));
+            Ok(val)
+        }
+
+        let mut stack = ContextStack::new();
+        assert_eq!(outer(Some(1), &mut stack), Ok(1));
+        assert!(stack.is_empty());
+
+        assert_eq!(outer(None, &mut stack), Err(()));
+        assert_eq!(
+            stack.as_slice(),
+            &["reading value", "parsing row", "loading None"]
+        );
+        assert_eq!(
+            stack.to_string(),
+            "loading None: parsing row: reading value"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn try_collect_return_stops_early() {
+        fn collect(input: Vec<Option<u32>>, pulled: &mut usize) -> Result<Vec<u32>, usize> {
+            let iter = input.into_iter().inspect(|_| *pulled += 1);
+            let vals = try_collect_return!(iter, Err(*pulled));
+            Ok(vals)
+        }
+
+        fn propagate(input: Vec<Result<u32, &'static str>>) -> Result<Vec<u32>, String> {
+            Ok(try_collect_return!(input))
+        }
+
+        let mut pulled = 0;
+        assert_eq!(collect(vec![Some(1), Some(2)], &mut pulled), Ok(vec![1, 2]));
+        let mut pulled = 0;
+        assert_eq!(collect(vec![Some(1), None, Some(3)], &mut pulled), Err(2));
+        assert_eq!(collect(vec![], &mut pulled), Ok(vec![]));
+
+        assert_eq!(propagate(vec![Ok(1), Ok(2)]), Ok(vec![1, 2]));
+        assert_eq!(
+            propagate(vec![Ok(1), Err("first"), Err("second")]),
+            Err("first".to_owned())
+        );
+    }
+
+    #[test]
+    fn try_mut_writes() {
+        use std::collections::HashMap;
+
+        fn set(vals: &mut [u32], i: usize, val: u32) {
+            *try_mut!(vals.get_mut(i)) = val;
+        }
+
+        fn first_even(vals: &mut [u32]) -> Option<&mut u32> {
+            let val = try_mut!(vals.iter_mut().find(|v| **v % 2 == 0), None);
+            Some(val)
+        }
+
+        fn incr(counts: &mut HashMap<&str, u32>, key: &str) -> Result<u32, ()> {
+            let count = try_mut!(counts.get_mut(key), Err(()));
+            *count += 1;
+            Ok(*count)
+        }
+
+        let mut vals = [1, 2, 3, 4];
+        set(&mut vals, 0, 10);
+        set(&mut vals, 10, 10);
+        assert_eq!(vals, [10, 2, 3, 4]);
+
+        *first_even(&mut vals).unwrap() = 7;
+        assert_eq!(vals, [7, 2, 3, 4]);
+        *first_even(&mut vals).unwrap() = 9;
+        assert_eq!(vals, [7, 9, 3, 4]);
+
+        let mut counts = HashMap::from([("a", 1)]);
+        assert_eq!(incr(&mut counts, "a"), Ok(2));
+        assert_eq!(incr(&mut counts, "b"), Err(()));
+        assert_eq!(counts["a"], 2);
+    }
+
+    #[test]
+    fn try_continue_count_by() {
+        use std::collections::{BTreeMap, HashMap};
+        use std::io;
+
+        let results = vec![
+            Ok(1),
+            Err(io::Error::from(io::ErrorKind::NotFound)),
+            Err(io::Error::from(io::ErrorKind::TimedOut)),
+            Ok(2),
+            Err(io::Error::from(io::ErrorKind::NotFound)),
+        ];
+
+        let mut counts = HashMap::new();
+        let mut sum = 0;
+        for res in results {
+            sum += try_continue!(res, count_by: |e| e.kind(), counters: &mut counts);
+        }
+        assert_eq!(sum, 3);
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&io::ErrorKind::NotFound], 2);
+        assert_eq!(counts[&io::ErrorKind::TimedOut], 1);
+
+        let mut counts = BTreeMap::new();
+        for val in [Some(1), None, None] {
+            let _: u32 = try_continue!(val, count_by: |_| "missing", counters: &mut counts);
+        }
+        assert_eq!(counts["missing"], 2);
+    }
+
+    #[test]
+    fn try_return_memo_fallback() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::OnceLock;
+
+        static FALLBACK: OnceLock<String> = OnceLock::new();
+        static COMPUTED: AtomicUsize = AtomicUsize::new(0);
+
+        fn compute_fallback() -> String {
+            COMPUTED.fetch_add(1, Ordering::SeqCst);
+            "fallback".repeat(3)
+        }
+
+        fn name(val: Result<String, ()>) -> String {
+            let val = try_return!(val, memo_fallback: FALLBACK, compute_fallback);
+            val.to_uppercase()
+        }
+
+        assert_eq!(name(Ok("a".to_owned())), "A");
+        assert_eq!(COMPUTED.load(Ordering::SeqCst), 0);
         for _ in 0..10 {
-            count += 1;
-            let val: u32 = try_continue!(Some(10));
-            assert_eq!(val, 10)
+            assert_eq!(name(Err(())), "fallbackfallbackfallback");
         }
-        assert_eq!(count, 10);
+        assert_eq!(COMPUTED.load(Ordering::SeqCst), 1);
+    }
 
-        let mut count = 0;
-        'outer: for _ in 0..10 {
-            count += 1;
-            for _ in 0..10 {
-                let val: u32 = try_continue!(Some(10), 'outer);
-                assert_eq!(val, 10);
+    #[test]
+    fn try_key_sorting() {
+        struct Row {
+            name: &'static str,
+            rank: Option<u32>,
+        }
+
+        let mut rows = [
+            Row {
+                name: "c",
+                rank: Some(2),
+            },
+            Row {
+                name: "a",
+                rank: None,
+            },
+            Row {
+                name: "b",
+                rank: Some(1),
+            },
+            Row {
+                name: "d",
+                rank: None,
+            },
+        ];
+        rows.sort_by_key(|row| try_key!(row.rank, u32::MAX));
+        let names = rows.iter().map(|row| row.name).collect::<Vec<_>>();
+        assert_eq!(names, vec!["b", "c", "a", "d"]);
+
+        let mut words = ["10", "x", "2"];
+        words.sort_by_key(|word| try_key!(word.parse::<i32>(), -1));
+        assert_eq!(words, ["x", "2", "10"]);
+    }
+
+    #[test]
+    fn try_return_question_mark_fallback() {
+        fn fallback(calls: &mut u32, val: Option<&str>) -> Option<u32> {
+            *calls += 1;
+            val?.parse().ok()
+        }
+
+        fn lookup(val: Option<u32>, default: Option<&str>, calls: &mut u32) -> Option<u32> {
+            let val = try_return!(val, Some(fallback(calls, default)? * 10));
+            Some(val)
+        }
+
+        fn nested(outer: Option<u32>, inner: Option<&str>) -> Result<u32, String> {
+            let val = try_return!(outer, {
+                let inner = try_return!(inner, Err("no inner".to_owned()));
+                Ok(inner.parse::<u32>().map_err(|e| e.to_string())? + 1)
+            });
+            Ok(val)
+        }
+
+        let mut calls = 0;
+        assert_eq!(lookup(Some(1), None, &mut calls), Some(1));
+        assert_eq!(calls, 0);
+        assert_eq!(lookup(None, Some("4"), &mut calls), Some(40));
+        assert_eq!(lookup(None, Some("x"), &mut calls), None);
+        assert_eq!(lookup(None, None, &mut calls), None);
+        assert_eq!(calls, 3);
+
+        assert_eq!(nested(Some(1), None), Ok(1));
+        assert_eq!(nested(None, Some("2")), Ok(3));
+        assert_eq!(nested(None, None), Err("no inner".to_owned()));
+        assert_eq!(
+            nested(None, Some("x")),
+            Err("invalid digit found in string".to_owned())
+        );
+    }
+
+    #[test]
+    fn try_or_insert_present_absent() {
+        use std::collections::{BTreeMap, HashMap};
+
+        let mut inserted = 0;
+        let mut map = HashMap::from([("a", vec![1])]);
+
+        try_or_insert!(map, "a", || {
+            inserted += 1;
+            vec![]
+        })
+        .push(2);
+        assert_eq!(inserted, 0);
+        assert_eq!(map["a"], vec![1, 2]);
+
+        try_or_insert!(map, "b", || {
+            inserted += 1;
+            vec![10]
+        },)
+        .push(11);
+        assert_eq!(inserted, 1);
+        assert_eq!(map["b"], vec![10, 11]);
+
+        let mut tree = BTreeMap::new();
+        let val = try_or_insert!(tree, 1, String::new);
+        val.push('x');
+        assert_eq!(tree[&1], "x");
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn try_all_dyn_boxed() {
+        use crate::TryAsOptionErased;
+
+        fn check(guards: &[Box<dyn TryAsOptionErased>]) -> Result<(), usize> {
+            try_all_dyn!(guards, Err(guards.len()));
+            Ok(())
+        }
+
+        fn unit(guards: &[&dyn TryAsOptionErased], checked: &mut bool) {
+            try_all_dyn!(guards);
+            *checked = true;
+        }
+
+        let mut guards: Vec<Box<dyn TryAsOptionErased>> = vec![
+            Box::new(Some(1)),
+            Box::new(Ok::<_, String>("a")),
+            Box::new(Some(vec![1u8])),
+        ];
+        assert_eq!(check(&guards), Ok(()));
+        assert_eq!(check(&[]), Ok(()));
+
+        guards.push(Box::new(Err::<u32, _>(std::io::Error::other("bad"))));
+        assert_eq!(check(&guards), Err(4));
+
+        let mut checked = false;
+        unit(&[&Some(1), &None::<u32>], &mut checked);
+        assert!(!checked);
+        unit(&[&Some(1), &Ok::<_, ()>(2)], &mut checked);
+        assert!(checked);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn try_validate_collects_all() {
+        #[derive(Debug, PartialEq)]
+        enum Invalid {
+            Name,
+            Port(u16),
+            Hosts,
+        }
+
+        fn name(name: &str) -> Result<(), Invalid> {
+            if name.is_empty() {
+                return Err(Invalid::Name);
             }
+            Ok(())
         }
-        assert_eq!(count, 10);
+
+        fn port(port: u16) -> Result<(), Invalid> {
+            if port < 1024 {
+                return Err(Invalid::Port(port));
+            }
+            Ok(())
+        }
+
+        fn validate(n: &str, p: u16, hosts: &[&str], ran: &mut u32) -> Result<(), String> {
+            try_validate!(
+                [name(n), port(p), {
+                    *ran += 1;
+                    if hosts.is_empty() {
+                        Err(Invalid::Hosts)
+                    } else {
+                        Ok(())
+                    }
+                },],
+                |errors: Vec<Invalid>| Err(format!("{:?}", errors))
+            );
+            Ok(())
+        }
+
+        let mut ran = 0;
+        assert_eq!(validate("app", 8080, &["a"], &mut ran), Ok(()));
+        assert_eq!(
+            validate("", 80, &["a"], &mut ran),
+            Err("[Name, Port(80)]".to_owned())
+        );
+        assert_eq!(
+            validate("", 22, &[], &mut ran),
+            Err("[Name, Port(22), Hosts]".to_owned())
+        );
+        assert_eq!(ran, 3);
+
+        fn unit(checks: [Result<(), u8>; 2], out: &mut Vec<u8>) {
+            try_validate!([checks[0], checks[1]], |errors| out.extend(errors));
+            out.push(0);
+        }
+
+        let mut out = Vec::new();
+        unit([Ok(()), Ok(())], &mut out);
+        unit([Err(1), Err(2)], &mut out);
+        assert_eq!(out, vec![0, 1, 2]);
     }
 
     #[test]
-    fn try_break_none() {
-        loop {
-            let _: u32 = try_break!(None);
+    #[cfg(feature = "alloc")]
+    fn try_return_any_downcast() {
+        use std::any::Any;
+        use std::fs::File;
+        use std::num::ParseIntError;
+        use std::sync::mpsc;
+
+        fn parse(s: &str) -> Result<u32, Box<dyn Any + Send>> {
+            Ok(try_return_any!(s.parse::<u32>()))
+        }
+
+        fn open(path: &str, errors: &mpsc::Sender<Box<dyn Any + Send>>) -> bool {
+            let opened: Result<File, _> = Err(std::io::Error::other(path.to_owned()));
+            let _file = try_return_any!(opened, |err| errors.send(err).is_err());
+            true
+        }
+
+        assert_eq!(parse("12").unwrap(), 12);
+        let err = parse("x").unwrap_err();
+        assert!(err.is::<ParseIntError>());
+        assert_eq!(
+            err.downcast::<ParseIntError>().unwrap().to_string(),
+            "invalid digit found in string"
+        );
+
+        let (tx, rx) = mpsc::channel();
+        assert!(!open("a.txt", &tx));
+        let _ = tx.send(parse("").unwrap_err());
+        drop(tx);
+
+        let errors = rx.iter().collect::<Vec<_>>();
+        assert_eq!(errors.len(), 2);
+        let io = errors[0].downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(io.to_string(), "a.txt");
+        assert!(errors[1].downcast_ref::<std::io::Error>().is_none());
+        assert!(errors[1].is::<ParseIntError>());
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn try_return_cow_fallbacks() {
+        use std::borrow::Cow;
+
+        fn greeting(name: Option<&'static str>) -> Cow<'static, str> {
+            let name = try_return!(name, borrowed: "hello");
+            Cow::Owned(format!("hello {}", name))
+        }
+
+        fn label(id: Result<u32, ()>, computed: &mut u32) -> Cow<'static, str> {
+            let id = try_return!(id, owned: {
+                *computed += 1;
+                "unknown".repeat(2)
+            });
+            Cow::Owned(id.to_string())
+        }
+
+        assert!(matches!(greeting(None), Cow::Borrowed("hello")));
+        assert_eq!(greeting(Some("a")), "hello a");
+
+        let mut computed = 0;
+        assert_eq!(label(Ok(1), &mut computed), "1");
+        assert_eq!(computed, 0);
+        let fallback = label(Err(()), &mut computed);
+        assert!(matches!(&fallback, Cow::Owned(s) if s == "unknownunknown"));
+        assert_eq!(computed, 1);
+    }
+
+    #[test]
+    fn try_return_user_envelope_fn() {
+        fn envelope(code: u16, fallback: u32) -> u32 {
+            code as u32 + fallback
+        }
+
+        fn lookup(val: Option<u32>) -> u32 {
+            try_return!(val, envelope(400, 4))
+        }
+
+        assert_eq!(lookup(Some(1)), 1);
+        assert_eq!(lookup(None), 404);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn try_stringify_err_to_string() {
+        #[derive(Debug)]
+        struct Missing(&'static str);
+
+        impl std::fmt::Display for Missing {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "missing {}", self.0)
+            }
+        }
+
+        fn lookup(key: &'static str) -> Result<u32, Missing> {
+            match key {
+                "a" => Ok(1),
+                key => Err(Missing(key)),
+            }
+        }
+
+        fn glue(key: &'static str, num: &str) -> Result<u32, String> {
+            let a = try_stringify_err!(lookup(key));
+            let b = try_stringify_err!(num.parse::<u32>(),);
+            Ok(a + b)
+        }
+
+        fn boxed(key: &'static str) -> Result<u32, Box<dyn std::error::Error>> {
+            Ok(try_stringify_err!(lookup(key)))
+        }
+
+        assert_eq!(glue("a", "2"), Ok(3));
+        assert_eq!(glue("b", "2"), Err("missing b".to_owned()));
+        assert_eq!(
+            glue("a", "-"),
+            Err("invalid digit found in string".to_owned())
+        );
+        assert_eq!(boxed("c").unwrap_err().to_string(), "missing c");
+    }
+
+    #[test]
+    fn try_continue_monotonic() {
+        let input = ["3", "1", "x", "4", "4", "2", "9", "", "6", "10"];
+
+        let mut last = None;
+        let mut increasing = Vec::new();
+        for s in input {
+            let val: u32 = try_continue!(s.parse(), monotonic: &mut last, ord: Greater);
+            increasing.push(val);
+        }
+        assert_eq!(increasing, [3, 4, 9, 10]);
+        assert_eq!(last, Some(10));
+
+        let mut last = Some(5.0);
+        let mut decreasing = Vec::new();
+        'outer: for chunk in [[4.0, f64::NAN], [6.0, 1.0]] {
+            for val in chunk {
+                let val = try_continue!(Some(val), monotonic: &mut last, ord: Less, 'outer);
+                decreasing.push(val);
+            }
+        }
+        assert_eq!(decreasing, [4.0]);
+        assert_eq!(last, Some(4.0));
+    }
+
+    #[test]
+    fn try_return_where() {
+        fn even(val: Option<u32>, calls: &mut u32) -> Option<u32> {
+            let val = try_return!(
+                val,
+                where: |v| {
+                    *calls += 1;
+                    v % 2 == 0
+                },
+                None
+            );
+            Some(val)
+        }
+
+        let mut calls = 0;
+        assert_eq!(even(Some(4), &mut calls), Some(4));
+        assert_eq!(even(Some(3), &mut calls), None);
+        assert_eq!(calls, 2);
+        assert_eq!(even(None, &mut calls), None);
+        assert_eq!(calls, 2);
+
+        fn short(s: Result<String, ()>) -> String {
+            try_return!(s, where: |s| s.len() < 4, "long".to_owned())
+        }
+        assert_eq!(short(Ok("abc".to_owned())), "abc");
+        assert_eq!(short(Ok("abcdef".to_owned())), "long");
+        assert_eq!(short(Err(())), "long");
+
+        fn unit(val: Option<u32>) {
+            let _ = try_return!(val, where: |&v| v > 0);
             panic!();
         }
+        unit(Some(0));
+        unit(None);
+    }
 
-        'outer: loop {
-            for _ in 0..10 {
-                let _: u32 = try_break!(None, 'outer);
-                panic!();
+    #[test]
+    fn try_peek_keeps_item() {
+        fn split_ident<I: Iterator<Item = char>>(
+            chars: &mut std::iter::Peekable<I>,
+            ident: &mut String,
+        ) -> bool {
+            loop {
+                let c = *try_peek!(chars, true);
+                if !c.is_alphanumeric() {
+                    return false;
+                }
+                ident.push(c);
+                chars.next();
             }
+        }
+
+        let mut chars = "ab1 cd".chars().peekable();
+        let mut ident = String::new();
+        assert!(!split_ident(&mut chars, &mut ident));
+        assert_eq!(ident, "ab1");
+        assert_eq!(chars.next(), Some(' '));
+
+        ident.clear();
+        assert!(split_ident(&mut chars, &mut ident));
+        assert_eq!(ident, "cd");
+        assert_eq!(chars.next(), None);
+
+        fn first(iter: &mut std::iter::Peekable<std::vec::IntoIter<u32>>) {
+            let _ = try_peek!(iter);
             panic!();
         }
+        let mut empty = Vec::new().into_iter().peekable();
+        first(&mut empty);
+
+        let mut nums = [1, 2].into_iter().peekable();
+        let peeked = (|| Some(*try_peek!(nums, None)))();
+        assert_eq!(peeked, Some(1));
+        assert_eq!(nums.next(), Some(1));
     }
 
     #[test]
-    fn try_break_some() {
-        for _ in 0..10 {
-            let val: u32 = try_break!(Some(10));
-            assert_eq!(val, 10)
+    fn try_continue_budget() {
+        fn process(input: &[Option<u32>], budget: &mut u32) -> Result<u32, usize> {
+            let mut sum = 0;
+            for (i, val) in input.iter().enumerate() {
+                sum += try_continue!(*val, budget: &mut *budget, on_exhausted: return Err(i));
+            }
+            Ok(sum)
         }
 
-        'outer: for _ in 0..10 {
-            for _ in 0..10 {
-                let val: u32 = try_break!(Some(10), 'outer);
-                assert_eq!(val, 10)
+        let input = [Some(1), None, Some(2), None, None, Some(3), None];
+        let mut budget = 4;
+        assert_eq!(process(&input, &mut budget), Ok(6));
+        assert_eq!(budget, 0);
+
+        let mut budget = 3;
+        assert_eq!(process(&input, &mut budget), Err(6));
+        assert_eq!(budget, 0);
+
+        let mut budget = 0;
+        assert_eq!(process(&input, &mut budget), Err(1));
+        assert_eq!(process(&[Some(5)], &mut budget), Ok(5));
+
+        let mut budget = 1;
+        let mut seen = Vec::new();
+        'outer: for row in [["1", "x", "2"], ["y", "3", "z"], ["4", "5", "6"]] {
+            for s in row {
+                let val: u32 =
+                    try_continue!(s.parse(), budget: &mut budget, on_exhausted: break 'outer);
+                seen.push(val);
             }
         }
+        assert_eq!(seen, [1, 2]);
     }
 }
@@ -0,0 +1,103 @@
+use core::fmt;
+use core::panic::Location;
+
+/// An error recording where a guard failed
+///
+/// This is the error returned by [`try_located!`](crate::try_located).
+///
+/// ```
+/// use try_utils::GuardError;
+///
+/// let err = GuardError::new();
+/// assert_eq!(err.location().line(), line!() - 1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuardError {
+    location: &'static Location<'static>,
+}
+
+impl GuardError {
+    /// Creates a new error at the location of the caller
+    #[track_caller]
+    pub fn new() -> Self {
+        GuardError {
+            location: Location::caller(),
+        }
+    }
+
+    /// Returns where the guard failed
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+}
+
+impl Default for GuardError {
+    #[track_caller]
+    fn default() -> Self {
+        GuardError::new()
+    }
+}
+
+impl fmt::Display for GuardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "guard failed at {}", self.location)
+    }
+}
+
+impl core::error::Error for GuardError {}
+
+/// Returns `Ok` with the value of an expression if it is `Some` or `Ok`,
+/// otherwise `Err` with a [`GuardError`] recording the location of the
+/// `try_located!`.
+///
+/// This doesn't affect control flow, so the result can be inspected or
+/// propagated with `?`.
+///
+/// ```
+/// use try_utils::try_located;
+///
+/// let err = try_located!(None::<u32>).unwrap_err();
+/// assert_eq!(err.location().line(), line!() - 1);
+/// assert_eq!(try_located!(Some(1)), Ok(1));
+/// ```
+#[macro_export]
+macro_rules! try_located {
+    ($e: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            ::core::option::Option::Some(v) => ::core::result::Result::Ok(v),
+            ::core::option::Option::None => ::core::result::Result::Err($crate::GuardError::new()),
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GuardError;
+
+    #[test]
+    fn try_located_call_line() {
+        fn find(vals: &[u32], val: u32) -> Result<usize, GuardError> {
+            let i = try_located!(vals.iter().position(|v| *v == val))?;
+            Ok(i)
+        }
+
+        let line = line!() - 4;
+        assert_eq!(find(&[1, 2, 3], 2), Ok(1));
+
+        let err = find(&[1, 2, 3], 4).unwrap_err();
+        assert_eq!(err.location().file(), file!());
+        assert_eq!(err.location().line(), line);
+        assert_eq!(
+            err.to_string(),
+            format!("guard failed at {}", err.location())
+        );
+
+        let first = try_located!(Err::<u32, _>(()));
+        let second = try_located!(Err::<u32, _>(()));
+        assert_eq!(
+            second.unwrap_err().location().line(),
+            first.unwrap_err().location().line() + 1
+        );
+    }
+}
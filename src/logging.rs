@@ -0,0 +1,368 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+#[doc(hidden)]
+pub use log as __log;
+
+/// When a rate limited guard last logged, in nanoseconds on [`now`]'s clock
+/// plus one so that `0` means never
+#[doc(hidden)]
+pub struct __RateLimit(AtomicU64);
+
+impl __RateLimit {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        __RateLimit(AtomicU64::new(0))
+    }
+
+    /// Returns `true` if nothing was allowed within the last `interval`
+    pub fn allow(&self, interval: Duration) -> bool {
+        self.allow_at(now(), interval)
+    }
+
+    fn allow_at(&self, now: u64, interval: Duration) -> bool {
+        let last = self.0.load(Ordering::Relaxed);
+        let interval = u64::try_from(interval.as_nanos()).unwrap_or(u64::MAX);
+        if last != 0 && now.saturating_sub(last - 1) < interval {
+            return false;
+        }
+        // only one of several threads racing past the window gets to log
+        self.0
+            .compare_exchange(
+                last,
+                now.saturating_add(1),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            )
+            .is_ok()
+    }
+}
+
+fn now() -> u64 {
+    static START: OnceLock<Instant> = OnceLock::new();
+    u64::try_from(START.get_or_init(Instant::now).elapsed().as_nanos()).unwrap_or(u64::MAX)
+}
+
+/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
+/// returns from the current function with the given value or `()` if none is
+/// given, logging a warning the first time this guard fails.
+///
+/// Each call site has its own `Once`, so a guard failing in a hot loop only
+/// logs once and later failures are silent.
+///
+/// ```
+/// use try_utils::try_return_once;
+///
+/// fn my_func(val: Option<i32>) -> i32 {
+///     let val = try_return_once!(val, "val was missing", 1234);
+///     val
+/// }
+/// for _ in 0..10 {
+///     assert_eq!(my_func(None), 1234); // only warns once
+/// }
+/// ```
+#[macro_export]
+macro_rules! try_return_once {
+    ($e: expr, $msg: expr) => {
+        $crate::try_return_once!($e, $msg, ())
+    };
+
+    ($e: expr, $msg: expr, $ret: expr) => {{
+        use $crate::TryAsOption;
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => {
+                static ONCE: ::std::sync::Once = ::std::sync::Once::new();
+                ONCE.call_once(|| $crate::__log::warn!("{}", $msg));
+                return $ret;
+            }
+        }
+    }};
+}
+
+#[cfg(test)]
+pub(crate) mod test_logger {
+    use std::sync::{Mutex, Once};
+
+    use log::{Level, Log, Metadata, Record};
+
+    static RECORDS: Mutex<Vec<(String, Level, String)>> = Mutex::new(Vec::new());
+
+    struct Capture;
+
+    impl Log for Capture {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            RECORDS.lock().unwrap().push((
+                record.target().to_owned(),
+                record.level(),
+                record.args().to_string(),
+            ));
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// Installs the capturing logger. Tests share it, so each should log to
+    /// its own target.
+    pub(crate) fn init() {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            log::set_logger(&Capture).unwrap();
+            log::set_max_level(log::LevelFilter::Trace);
+        });
+    }
+
+    /// Returns the level and message of everything logged to the target
+    pub(crate) fn captured(target: &str) -> Vec<(Level, String)> {
+        RECORDS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(t, _, _)| t == target)
+            .map(|(_, level, msg)| (*level, msg.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use log::Level;
+
+    use super::test_logger::{captured, init};
+    use crate::try_return;
+
+    #[test]
+    fn try_return_target() {
+        fn guarded(val: Option<u32>) -> u32 {
+            try_return!(val, target: "try_utils::test::db", level: Level::Warn, 0)
+        }
+
+        fn unit(val: Result<u32, ()>) {
+            let _ = try_return!(val, target: "try_utils::test::unit", level: Level::Error);
+        }
+
+        init();
+        assert_eq!(guarded(Some(10)), 10);
+        assert!(captured("try_utils::test::db").is_empty());
+
+        assert_eq!(guarded(None), 0);
+        assert_eq!(
+            captured("try_utils::test::db"),
+            vec![(Level::Warn, "guard failed: val".to_owned())]
+        );
+
+        unit(Err(()));
+        assert_eq!(
+            captured("try_utils::test::unit"),
+            vec![(Level::Error, "guard failed: val".to_owned())]
+        );
+    }
+
+    #[test]
+    fn try_return_once_warns_once() {
+        fn guarded(val: Option<u32>) -> u32 {
+            try_return_once!(val, "first failure", 0)
+        }
+
+        fn other(val: Result<u32, ()>) {
+            let _ = try_return_once!(val, "other failure");
+        }
+
+        init();
+        assert_eq!(guarded(Some(10)), 10);
+        for _ in 0..100 {
+            assert_eq!(guarded(None), 0);
+            other(Err(()));
+        }
+
+        let target = module_path!();
+        let warnings = captured(target);
+        assert_eq!(
+            warnings,
+            vec![
+                (Level::Warn, "first failure".to_owned()),
+                (Level::Warn, "other failure".to_owned())
+            ]
+        );
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct MyError(String);
+
+    impl From<std::num::ParseIntError> for MyError {
+        fn from(e: std::num::ParseIntError) -> Self {
+            MyError(e.to_string())
+        }
+    }
+
+    // Logs to its own target so other tests' records don't interfere
+    mod mapped {
+        use log::Level;
+
+        use super::MyError;
+        use crate::try_return;
+
+        pub(super) fn parse(s: &str) -> Result<u32, MyError> {
+            let val = try_return!(s.parse::<u32>(), log_and_map: (Level::Error, MyError::from));
+            Ok(val)
+        }
+    }
+
+    #[test]
+    fn try_return_log_and_map() {
+        init();
+        let target = concat!(module_path!(), "::mapped");
+        assert_eq!(mapped::parse("12"), Ok(12));
+        assert!(captured(target).is_empty());
+
+        assert_eq!(
+            mapped::parse("x"),
+            Err(MyError("invalid digit found in string".to_owned()))
+        );
+        assert_eq!(
+            captured(target),
+            vec![(
+                Level::Error,
+                "guard failed: s.parse::<u32>(): invalid digit found in string".to_owned()
+            )]
+        );
+    }
+
+    mod rate_limited {
+        use std::time::Duration;
+
+        use crate::try_return;
+
+        pub(super) fn guarded(val: Option<u32>) -> u32 {
+            try_return!(val, rate_limited_log: (Duration::from_secs(3600), "rate limited"), 0)
+        }
+
+        pub(super) fn other(val: Option<u32>) {
+            let _ = try_return!(val, rate_limited_log: (Duration::from_secs(3600), "other site"));
+        }
+
+        pub(super) fn unlimited(val: Option<u32>) {
+            let _ = try_return!(val, rate_limited_log: (Duration::ZERO, "unlimited"));
+        }
+    }
+
+    #[test]
+    fn try_return_rate_limited_log() {
+        init();
+        let target = concat!(module_path!(), "::rate_limited");
+        let count = |msg: &str| {
+            captured(target)
+                .iter()
+                .filter(|(level, m)| *level == Level::Warn && m == msg)
+                .count()
+        };
+
+        assert_eq!(rate_limited::guarded(Some(3)), 3);
+        assert_eq!(count("rate limited"), 0);
+
+        for _ in 0..100 {
+            assert_eq!(rate_limited::guarded(None), 0);
+        }
+        assert_eq!(count("rate limited"), 1);
+
+        // each call site has its own window
+        rate_limited::other(None);
+        rate_limited::other(None);
+        assert_eq!(count("other site"), 1);
+
+        for _ in 0..3 {
+            rate_limited::unlimited(None);
+        }
+        rate_limited::unlimited(Some(1));
+        assert_eq!(count("unlimited"), 3);
+    }
+
+    #[test]
+    fn rate_limit_window() {
+        use std::time::Duration;
+
+        use super::__RateLimit;
+
+        let ms = |ms: u64| ms * 1_000_000;
+        let interval = Duration::from_secs(1);
+        let limit = __RateLimit::new();
+        assert!(limit.allow_at(ms(0), interval));
+        assert!(!limit.allow_at(ms(0), interval));
+        assert!(!limit.allow_at(ms(999), interval));
+        assert!(limit.allow_at(ms(1000), interval));
+        assert!(!limit.allow_at(ms(1500), interval));
+        assert!(limit.allow_at(ms(7000), interval));
+
+        // a clock reading before the last allowed one stays limited
+        assert!(!limit.allow_at(ms(6000), interval));
+
+        // intervals too long for u64 nanoseconds never let a second one through
+        let limit = __RateLimit::new();
+        assert!(limit.allow_at(ms(0), Duration::MAX));
+        assert!(!limit.allow_at(ms(7000), Duration::MAX));
+    }
+
+    mod dedup {
+        use std::collections::{BTreeSet, HashSet};
+
+        use crate::try_continue;
+
+        pub(super) fn sum(input: &[&str], seen: &mut HashSet<String>) -> u32 {
+            let mut sum = 0;
+            for s in input {
+                sum += try_continue!(s.parse::<u32>(), log_dedup: &mut *seen);
+            }
+            sum
+        }
+
+        pub(super) fn count_ok(input: &[Result<(), &str>]) -> usize {
+            let mut seen = BTreeSet::new();
+            let mut ok = 0;
+            for res in input {
+                try_continue!(*res, log_dedup: &mut seen);
+                ok += 1;
+            }
+            ok
+        }
+    }
+
+    #[test]
+    fn try_continue_log_dedup() {
+        init();
+        let target = concat!(module_path!(), "::dedup");
+        let mut seen = std::collections::HashSet::new();
+        assert_eq!(
+            dedup::sum(&["1", "x", "2", "y", "-", "x", "3"], &mut seen),
+            6
+        );
+        assert_eq!(seen.len(), 1);
+        assert_eq!(
+            captured(target),
+            vec![(
+                Level::Warn,
+                "guard failed: s.parse::<u32>(): invalid digit found in string".to_owned()
+            )]
+        );
+
+        assert_eq!(dedup::sum(&["", "", "4"], &mut seen), 4);
+        assert_eq!(seen.len(), 2);
+        assert_eq!(captured(target).len(), 2);
+        assert!(captured(target)[1]
+            .1
+            .ends_with(": cannot parse integer from empty string"));
+
+        assert_eq!(
+            dedup::count_ok(&[Ok(()), Err("a"), Err("b"), Err("a"), Ok(())]),
+            2
+        );
+        let logged = captured(target);
+        assert_eq!(logged.len(), 4);
+        assert_eq!(logged[2].1, "guard failed: *res: a");
+        assert_eq!(logged[3].1, "guard failed: *res: b");
+    }
+}
@@ -0,0 +1,218 @@
+/// Adds two integers with `checked_add`, otherwise returns from the
+/// current function with the given value or `()` if none is given on
+/// an overflow.
+///
+/// ```
+/// use try_utils::try_add;
+///
+/// fn add(a: u8, b: u8) -> Option<u8> {
+///     Some(try_add!(a, b, None))
+/// }
+/// assert!(add(u8::MAX - 1, 1).is_some());
+/// assert_eq!(add(u8::MAX, 1), None);
+/// ```
+#[macro_export]
+macro_rules! try_add {
+    ($a: expr, $b: expr) => {
+        $crate::try_add!($a, $b, ())
+    };
+
+    ($a: expr, $b: expr, $ret: expr) => {
+        match $a.checked_add($b) {
+            ::core::option::Option::Some(v) => v,
+            ::core::option::Option::None => return $ret,
+        }
+    };
+}
+
+/// Subtracts two integers with `checked_sub`, otherwise returns from the
+/// current function with the given value or `()` if none is given on
+/// an overflow.
+///
+/// ```
+/// use try_utils::try_sub;
+///
+/// fn sub(a: u8, b: u8) -> Option<u8> {
+///     Some(try_sub!(a, b, None))
+/// }
+/// assert!(sub(1u8, 1).is_some());
+/// assert_eq!(sub(0u8, 1), None);
+/// ```
+#[macro_export]
+macro_rules! try_sub {
+    ($a: expr, $b: expr) => {
+        $crate::try_sub!($a, $b, ())
+    };
+
+    ($a: expr, $b: expr, $ret: expr) => {
+        match $a.checked_sub($b) {
+            ::core::option::Option::Some(v) => v,
+            ::core::option::Option::None => return $ret,
+        }
+    };
+}
+
+/// Multiplies two integers with `checked_mul`, otherwise returns from the
+/// current function with the given value or `()` if none is given on
+/// an overflow.
+///
+/// ```
+/// use try_utils::try_mul;
+///
+/// fn mul(a: u8, b: u8) -> Option<u8> {
+///     Some(try_mul!(a, b, None))
+/// }
+/// assert!(mul(16u8, 15).is_some());
+/// assert_eq!(mul(16u8, 16), None);
+/// ```
+#[macro_export]
+macro_rules! try_mul {
+    ($a: expr, $b: expr) => {
+        $crate::try_mul!($a, $b, ())
+    };
+
+    ($a: expr, $b: expr, $ret: expr) => {
+        match $a.checked_mul($b) {
+            ::core::option::Option::Some(v) => v,
+            ::core::option::Option::None => return $ret,
+        }
+    };
+}
+
+/// Divides two integers with `checked_div`, otherwise returns from the
+/// current function with the given value or `()` if none is given on
+/// division by zero or an overflow.
+///
+/// ```
+/// use try_utils::try_div;
+///
+/// fn div(a: u8, b: u8) -> Option<u8> {
+///     Some(try_div!(a, b, None))
+/// }
+/// assert!(div(10u8, 3).is_some());
+/// assert_eq!(div(10u8, 0), None);
+/// ```
+#[macro_export]
+macro_rules! try_div {
+    ($a: expr, $b: expr) => {
+        $crate::try_div!($a, $b, ())
+    };
+
+    ($a: expr, $b: expr, $ret: expr) => {
+        match $a.checked_div($b) {
+            ::core::option::Option::Some(v) => v,
+            ::core::option::Option::None => return $ret,
+        }
+    };
+}
+
+/// Returns `depth + 1` if `depth < max`, otherwise returns from the current
+/// function with the given value or `()` if none is given.
+///
+/// This bounds recursion when used at the top of a recursive function that
+/// passes the result on to its recursive calls.
+///
+/// ```
+/// use try_utils::try_depth;
+///
+/// fn count_down(n: u32, depth: u32) -> Option<u32> {
+///     let depth = try_depth!(depth, 8, None);
+///     if n == 0 {
+///         return Some(depth);
+///     }
+///     count_down(n - 1, depth)
+/// }
+/// assert_eq!(count_down(3, 0), Some(4));
+/// assert_eq!(count_down(100, 0), None);
+/// ```
+#[macro_export]
+macro_rules! try_depth {
+    ($depth: expr, $max: expr) => {
+        $crate::try_depth!($depth, $max, ())
+    };
+
+    ($depth: expr, $max: expr, $ret: expr) => {{
+        let depth = $depth;
+        if depth >= $max {
+            return $ret;
+        }
+        depth + 1
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    fn checked(a: i32, b: i32) -> Option<[i32; 4]> {
+        Some([
+            try_add!(a, b, None),
+            try_sub!(a, b, None),
+            try_mul!(a, b, None),
+            try_div!(a, b, None),
+        ])
+    }
+
+    #[test]
+    fn try_checked_boundaries() {
+        assert_eq!(checked(6, 3), Some([9, 3, 18, 2]));
+        assert_eq!(
+            checked(i32::MAX - 1, 1),
+            Some([i32::MAX, i32::MAX - 2, i32::MAX - 1, i32::MAX - 1])
+        );
+
+        assert_eq!(checked(i32::MAX, 1), None);
+        assert_eq!(checked(i32::MIN, 1), None);
+        assert_eq!(checked(i32::MAX / 2 + 1, 2), None);
+        assert_eq!(checked(5, 0), None);
+        assert_eq!(checked(i32::MIN, -1), None);
+    }
+
+    #[test]
+    fn try_checked_unit() {
+        fn accumulate(total: &mut u8, vals: &[u8]) {
+            for &val in vals {
+                *total = try_add!(*total, val);
+            }
+        }
+
+        let mut total = 0;
+        accumulate(&mut total, &[100, 100, 55]);
+        assert_eq!(total, u8::MAX);
+        accumulate(&mut total, &[1]);
+        assert_eq!(total, u8::MAX);
+    }
+
+    #[test]
+    fn try_depth_limit() {
+        enum Tree {
+            Leaf,
+            Node(Box<Tree>),
+        }
+
+        fn height(tree: &Tree, depth: usize) -> Result<usize, usize> {
+            let depth = try_depth!(depth, 4, Err(depth));
+            match tree {
+                Tree::Leaf => Ok(depth),
+                Tree::Node(child) => height(child, depth),
+            }
+        }
+
+        fn nested(n: usize) -> Tree {
+            (0..n).fold(Tree::Leaf, |tree, _| Tree::Node(Box::new(tree)))
+        }
+
+        assert_eq!(height(&nested(0), 0), Ok(1));
+        assert_eq!(height(&nested(3), 0), Ok(4));
+        assert_eq!(height(&nested(4), 0), Err(4));
+        assert_eq!(height(&nested(100), 0), Err(4));
+
+        fn visit(depth: u8, visited: &mut u8) {
+            let depth = try_depth!(depth, 3);
+            *visited += 1;
+            visit(depth, visited);
+        }
+
+        let mut visited = 0;
+        visit(0, &mut visited);
+        assert_eq!(visited, 3);
+    }
+}
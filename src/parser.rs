@@ -0,0 +1,74 @@
+#[doc(hidden)]
+pub use nom as __nom;
+
+/// Returns the remaining input and output of a `nom` parser if it succeeded,
+/// otherwise returns from the current function with the given value or `()`
+/// if none is given on an error, failure or incomplete input.
+///
+/// ```
+/// use nom::character::complete::digit1;
+/// use try_utils::try_parse_nom;
+///
+/// fn number(input: &str) -> Option<(&str, u32)> {
+///     let (rest, digits) = try_parse_nom!(digit1::<_, ()>(input), None);
+///     Some((rest, digits.parse().ok()?))
+/// }
+/// assert_eq!(number("42abc"), Some(("abc", 42)));
+/// assert_eq!(number("abc"), None);
+/// ```
+#[macro_export]
+macro_rules! try_parse_nom {
+    ($e: expr) => {
+        $crate::try_parse_nom!($e, ())
+    };
+
+    ($e: expr, $ret: expr) => {
+        match $e {
+            ::core::result::Result::Ok((rest, output)) => (rest, output),
+            ::core::result::Result::Err(
+                $crate::__nom::Err::Error(_)
+                | $crate::__nom::Err::Failure(_)
+                | $crate::__nom::Err::Incomplete(_),
+            ) => return $ret,
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::bytes::complete::tag;
+    use nom::bytes::streaming;
+    use nom::character::complete::{alpha1, digit1};
+    use nom::error::Error;
+    use nom::{IResult, Parser};
+
+    fn key_value(input: &str) -> IResult<&str, (&str, &str)> {
+        (alpha1, tag("="), digit1)
+            .map(|(k, _, v)| (k, v))
+            .parse(input)
+    }
+
+    #[test]
+    fn try_parse_nom_success_and_failure() {
+        fn parse(input: &str) -> Result<(&str, &str, &str), &'static str> {
+            let (rest, (k, v)) = try_parse_nom!(key_value(input), Err("bad pair"));
+            Ok((rest, k, v))
+        }
+
+        assert_eq!(parse("a=1;b=2"), Ok((";b=2", "a", "1")));
+        assert_eq!(parse("a:1"), Err("bad pair"));
+        assert_eq!(parse("=1"), Err("bad pair"));
+    }
+
+    #[test]
+    fn try_parse_nom_incomplete() {
+        fn header(input: &[u8]) -> Option<&[u8]> {
+            let (rest, _) = try_parse_nom!(streaming::tag::<_, _, Error<_>>("HEAD")(input), None);
+            Some(rest)
+        }
+
+        assert_eq!(header(b"HEAD /"), Some(&b" /"[..]));
+        assert_eq!(header(b"HE"), None);
+        assert_eq!(header(b"GET /"), None);
+    }
+}
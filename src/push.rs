@@ -0,0 +1,119 @@
+/// A trait for collections that can refuse a push, used by [`try_push!`]
+///
+/// [`try_push!`]: crate::try_push
+pub trait TryPush {
+    type Item;
+    /// Pushes an item, handing it back if the collection can't hold it
+    fn try_push(&mut self, item: Self::Item) -> Result<(), Self::Item>;
+}
+
+#[cfg(feature = "arrayvec")]
+impl<T, const CAP: usize> TryPush for arrayvec::ArrayVec<T, CAP> {
+    type Item = T;
+    fn try_push(&mut self, item: Self::Item) -> Result<(), Self::Item> {
+        arrayvec::ArrayVec::try_push(self, item).map_err(|e| e.element())
+    }
+}
+
+/// A `SmallVec` only refuses a push if it can't allocate room for the item
+/// after spilling to the heap, so pushing is effectively infallible.
+#[cfg(feature = "smallvec")]
+impl<A: smallvec::Array> TryPush for smallvec::SmallVec<A> {
+    type Item = A::Item;
+    fn try_push(&mut self, item: Self::Item) -> Result<(), Self::Item> {
+        match self.try_reserve(1) {
+            Ok(()) => {
+                self.push(item);
+                Ok(())
+            }
+            Err(_) => Err(item),
+        }
+    }
+}
+
+/// Pushes an item into a bounded collection, otherwise returns from the
+/// current function with the given value or `()` if none is given.
+///
+/// The collection is given as a `&mut` reference and must implement
+/// [`TryPush`], which is provided for `arrayvec::ArrayVec` and
+/// `smallvec::SmallVec` behind the `arrayvec` and `smallvec` features. A
+/// `SmallVec` spills to the heap when full, so it only fails if allocating
+/// fails and the guard is effectively infallible.
+///
+/// ```
+/// # #[cfg(feature = "arrayvec")] {
+/// use arrayvec::ArrayVec;
+/// use try_utils::try_push;
+///
+/// fn fill(buf: &mut ArrayVec<i32, 3>, items: &[i32]) -> bool {
+///     for &item in items {
+///         try_push!(buf, item, false);
+///     }
+///     true
+/// }
+///
+/// let mut buf = ArrayVec::new();
+/// assert!(fill(&mut buf, &[1, 2]));
+/// assert!(!fill(&mut buf, &[3, 4]));
+/// assert_eq!(buf.as_slice(), &[1, 2, 3]);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! try_push {
+    ($vec: expr, $item: expr) => {
+        $crate::try_push!($vec, $item, ())
+    };
+
+    ($vec: expr, $item: expr, $ret: expr) => {
+        match $crate::TryPush::try_push($vec, $item) {
+            ::core::result::Result::Ok(()) => (),
+            ::core::result::Result::Err(_) => return $ret,
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "arrayvec")]
+    #[test]
+    fn try_push_arrayvec() {
+        use arrayvec::ArrayVec;
+
+        fn fill(buf: &mut ArrayVec<u32, 4>) -> usize {
+            for i in 0.. {
+                try_push!(buf, i, i as usize);
+            }
+            unreachable!()
+        }
+
+        let mut buf = ArrayVec::new();
+        assert_eq!(fill(&mut buf), 4);
+        assert!(buf.is_full());
+        assert_eq!(buf.as_slice(), &[0, 1, 2, 3]);
+
+        fn local() -> bool {
+            let mut buf = ArrayVec::<u32, 1>::new();
+            try_push!(&mut buf, 1, false);
+            try_push!(&mut buf, 2, true);
+            false
+        }
+        assert!(local());
+    }
+
+    #[cfg(feature = "smallvec")]
+    #[test]
+    fn try_push_smallvec() {
+        use smallvec::SmallVec;
+
+        fn fill(buf: &mut SmallVec<[u32; 2]>) {
+            for i in 0..4 {
+                try_push!(buf, i);
+            }
+        }
+
+        let mut buf = SmallVec::new();
+        fill(&mut buf);
+        assert!(buf.spilled());
+        assert_eq!(buf.as_slice(), &[0, 1, 2, 3]);
+    }
+}
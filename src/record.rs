@@ -0,0 +1,163 @@
+use std::fmt::{self, Write as _};
+use std::io::{self, Write};
+use std::panic::Location;
+
+/// Writes a guard failure as a single line JSON object with the event, the
+/// guarded expression and the location of the guard.
+///
+/// The line is built in a buffer first and written with one call, so records
+/// from different threads don't interleave.
+pub(crate) fn write_json_line(
+    w: &mut impl Write,
+    event: &str,
+    guard: &str,
+    location: &Location<'_>,
+) -> io::Result<()> {
+    let mut line = String::with_capacity(64 + event.len() + guard.len());
+    line.push_str("{\"event\":");
+    push_json_str(&mut line, event);
+    line.push_str(",\"guard\":");
+    push_json_str(&mut line, guard);
+    line.push_str(",\"file\":");
+    push_json_str(&mut line, location.file());
+    let _ = write!(line, ",\"line\":{}}}", location.line());
+    line.push('\n');
+    w.write_all(line.as_bytes())
+}
+
+fn push_json_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn __stderr_json(event: &str, guard: &str) {
+    // there is nowhere left to report a failure to write to stderr
+    let _ = write_json_line(&mut io::stderr(), event, guard, Location::caller());
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn __debug_dump(guard: &str, context: &impl fmt::Debug) {
+    let location = Location::caller();
+    let dump = format!("guard failed at {}: {}\n{:#?}\n", location, guard, context);
+    let _ = io::stderr().write_all(dump.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+    use crate::try_return;
+
+    #[test]
+    fn write_json_line_escapes() {
+        let mut out = Vec::new();
+        let location = Location::caller();
+        write_json_line(&mut out, "bad \"value\"\n", "map[\"k\"]\\\u{1}", location).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.ends_with('\n'));
+        assert_eq!(out.lines().count(), 1);
+
+        let record: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(record["event"], "bad \"value\"\n");
+        assert_eq!(record["guard"], "map[\"k\"]\\\u{1}");
+        assert_eq!(record["file"], location.file());
+        assert_eq!(record["line"], location.line());
+    }
+
+    fn guarded(val: Option<u32>) -> u32 {
+        try_return!(val, stderr_json: "missing_value", 0)
+    }
+
+    #[test]
+    fn try_return_stderr_json_child() {
+        if !is_child() {
+            return;
+        }
+        assert_eq!(guarded(Some(1)), 1);
+        assert_eq!(guarded(None), 0);
+    }
+
+    /// Runs a test in a child process with `TRY_UTILS_CHILD` set, returning
+    /// its stderr since stderr can't be captured in process
+    fn run_child(test: &str) -> String {
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", test, "--nocapture", "--test-threads=1"])
+            .env("TRY_UTILS_CHILD", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stderr).unwrap()
+    }
+    fn is_child() -> bool {
+        std::env::var_os("TRY_UTILS_CHILD").is_some()
+    }
+
+    #[test]
+    fn try_return_stderr_json() {
+        let stderr = run_child("record::tests::try_return_stderr_json_child");
+
+        let records = stderr
+            .lines()
+            .filter(|line| line.starts_with('{'))
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["event"], "missing_value");
+        assert_eq!(records[0]["guard"], "val");
+        assert_eq!(records[0]["file"], file!());
+    }
+
+    #[test]
+    fn try_return_debug_dump_child() {
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        struct Request {
+            id: u32,
+            path: &'static str,
+        }
+
+        fn handle(val: Option<u32>, request: &Request) -> u32 {
+            try_return!(val, 0, debug_dump: request)
+        }
+
+        if !is_child() {
+            return;
+        }
+        let request = Request {
+            id: 7,
+            path: "/items",
+        };
+        assert_eq!(handle(Some(1), &request), 1);
+        assert_eq!(handle(None, &request), 0);
+    }
+
+    #[test]
+    fn try_return_debug_dump() {
+        let stderr = run_child("record::tests::try_return_debug_dump_child");
+        if cfg!(debug_assertions) {
+            assert_eq!(stderr.matches("guard failed at ").count(), 1);
+            assert!(stderr.contains(&format!("{}:", file!())));
+            assert!(stderr.contains(": val\nRequest {\n    id: 7,\n    path: \"/items\",\n}\n"));
+        } else {
+            assert!(!stderr.contains("guard failed"));
+        }
+    }
+}
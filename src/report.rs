@@ -0,0 +1,121 @@
+use core::fmt::Display;
+
+use eyre::{Report, WrapErr};
+
+/// Converts a failed guard into an [`eyre::Report`] carrying the given
+/// context
+#[doc(hidden)]
+pub trait __WrapEyre {
+    type Output;
+    fn wrap_eyre<D>(self, context: D) -> Result<Self::Output, Report>
+    where
+        D: Display + Send + Sync + 'static;
+}
+
+impl<T> __WrapEyre for Option<T> {
+    type Output = T;
+
+    fn wrap_eyre<D>(self, context: D) -> Result<T, Report>
+    where
+        D: Display + Send + Sync + 'static,
+    {
+        self.ok_or_else(|| eyre::eyre!("{}", context))
+    }
+}
+
+impl<T, E> __WrapEyre for Result<T, E>
+where
+    Result<T, E>: WrapErr<T, E>,
+{
+    type Output = T;
+
+    fn wrap_eyre<D>(self, context: D) -> Result<T, Report>
+    where
+        D: Display + Send + Sync + 'static,
+    {
+        self.wrap_err(context)
+    }
+}
+
+/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
+/// returns from the current function with `Err` of an [`eyre::Report`].
+///
+/// For `None` the report is just the given context. For `Err` the error is
+/// wrapped with the context using [`WrapErr::wrap_err`], so it stays in the
+/// report's chain of causes.
+///
+/// ```
+/// use try_utils::try_return_eyre;
+///
+/// fn port(s: &str) -> eyre::Result<u16> {
+///     let port = try_return_eyre!(s.parse::<u16>(), "invalid port");
+///     Ok(port)
+/// }
+/// let report = port("x").unwrap_err();
+/// assert_eq!(report.to_string(), "invalid port");
+/// assert_eq!(report.root_cause().to_string(), "invalid digit found in string");
+/// ```
+///
+/// [`WrapErr::wrap_err`]: eyre::WrapErr::wrap_err
+#[macro_export]
+macro_rules! try_return_eyre {
+    ($e: expr, $context: expr $(,)?) => {
+        match $crate::__WrapEyre::wrap_eyre($e, $context) {
+            ::core::result::Result::Ok(v) => v,
+            ::core::result::Result::Err(report) => {
+                return ::core::result::Result::Err(::core::convert::From::from(report))
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt;
+
+    use eyre::eyre;
+
+    #[derive(Debug)]
+    struct Timeout(u32);
+
+    impl fmt::Display for Timeout {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "timed out after {}ms", self.0)
+        }
+    }
+
+    impl std::error::Error for Timeout {}
+
+    fn connect(host: Option<&str>, res: Result<u32, Timeout>) -> eyre::Result<u32> {
+        let host = try_return_eyre!(host, "no host given");
+        let conn = try_return_eyre!(res, format!("connecting to {}", host));
+        Ok(conn)
+    }
+
+    #[test]
+    fn try_return_eyre_context() {
+        assert_eq!(connect(Some("a"), Ok(3)).unwrap(), 3);
+
+        let report = connect(None, Ok(3)).unwrap_err();
+        assert_eq!(report.to_string(), "no host given");
+        assert_eq!(report.chain().count(), 1);
+
+        let report = connect(Some("db"), Err(Timeout(50))).unwrap_err();
+        assert_eq!(report.to_string(), "connecting to db");
+        let chain = report.chain().map(|e| e.to_string()).collect::<Vec<_>>();
+        assert_eq!(chain, ["connecting to db", "timed out after 50ms"]);
+        assert!(report.downcast_ref::<Timeout>().is_some());
+        assert!(format!("{:?}", report).contains("timed out after 50ms"));
+    }
+
+    #[test]
+    fn try_return_eyre_report() {
+        fn load(res: eyre::Result<u32>) -> eyre::Result<u32> {
+            Ok(try_return_eyre!(res, "loading config"))
+        }
+
+        let report = load(Err(eyre!("file missing"))).unwrap_err();
+        let chain = report.chain().map(|e| e.to_string()).collect::<Vec<_>>();
+        assert_eq!(chain, ["loading config", "file missing"]);
+    }
+}
@@ -0,0 +1,117 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fmt;
+use std::panic::Location;
+use std::sync::Mutex;
+
+/// The number of failures kept by the ring log
+///
+/// Older failures are dropped once this many have been recorded. Enable the
+/// `ringlog-large` feature to keep more.
+pub const RINGLOG_CAPACITY: usize = if cfg!(feature = "ringlog-large") {
+    1024
+} else {
+    32
+};
+
+static RINGLOG: Mutex<VecDeque<Failure>> = Mutex::new(VecDeque::new());
+
+/// A guard failure recorded by `try_return!(expr, ringlog: "msg", ret)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    /// The message given to the guard
+    pub message: Cow<'static, str>,
+    /// Where the guard that failed is
+    pub location: &'static Location<'static>,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+/// Returns the most recent guard failures recorded in the ring log, oldest
+/// first.
+///
+/// ```
+/// use try_utils::try_return;
+///
+/// fn my_func(val: Option<i32>) -> i32 {
+///     let val = try_return!(val, ringlog: "val was missing", 1234);
+///     val
+/// }
+/// my_func(None);
+///
+/// let failures = try_utils::recent_failures();
+/// assert_eq!(failures.last().unwrap().message, "val was missing");
+/// ```
+pub fn recent_failures() -> Vec<Failure> {
+    lock().iter().cloned().collect()
+}
+
+/// Removes all failures from the ring log
+pub fn clear_failures() {
+    lock().clear();
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn __ringlog_push(message: impl Into<Cow<'static, str>>) {
+    let failure = Failure {
+        message: message.into(),
+        location: Location::caller(),
+    };
+    let mut log = lock();
+    if log.len() == RINGLOG_CAPACITY {
+        log.pop_front();
+    }
+    log.push_back(failure);
+}
+
+fn lock() -> std::sync::MutexGuard<'static, VecDeque<Failure>> {
+    // recording a failure can't leave the log in a bad state
+    RINGLOG.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::try_return;
+
+    // the ring log is global, so everything touching it lives in this test
+    #[test]
+    fn try_return_ringlog() {
+        fn guarded(val: Option<u32>, i: usize) -> u32 {
+            try_return!(val, ringlog: format!("failure {}", i), 0)
+        }
+
+        fn unit(val: Result<u32, ()>) {
+            let _ = try_return!(val, ringlog: "unit");
+        }
+
+        clear_failures();
+        assert_eq!(guarded(Some(10), 0), 10);
+        assert!(recent_failures().is_empty());
+
+        unit(Err(()));
+        let failures = recent_failures();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].message, "unit");
+        assert_eq!(failures[0].location.file(), file!());
+
+        for i in 0..RINGLOG_CAPACITY + 5 {
+            assert_eq!(guarded(None, i), 0);
+        }
+        let failures = recent_failures();
+        assert_eq!(failures.len(), RINGLOG_CAPACITY);
+        assert_eq!(failures[0].message, "failure 5");
+        assert_eq!(
+            failures.last().unwrap().message,
+            format!("failure {}", RINGLOG_CAPACITY + 4)
+        );
+
+        clear_failures();
+        assert!(recent_failures().is_empty());
+    }
+}
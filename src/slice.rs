@@ -0,0 +1,101 @@
+#[doc(hidden)]
+pub fn __window<T, const LEN: usize>(slice: &[T], start: usize) -> Option<&[T; LEN]> {
+    slice.get(start..)?.get(..LEN)?.try_into().ok()
+}
+
+/// Returns a reference to the `LEN` elements of a slice starting at `start`
+/// as an array if they are in bounds, otherwise returns from the current
+/// function with the given value or `()` if none is given.
+///
+/// `LEN` must be a constant. This is for reading fixed width fields, for
+/// example in binary protocol parsers.
+///
+/// ```
+/// use try_utils::try_window;
+///
+/// fn read_u32(buf: &[u8], at: usize) -> Option<u32> {
+///     let bytes = try_window!(buf, at, 4, None);
+///     Some(u32::from_be_bytes(*bytes))
+/// }
+/// assert_eq!(read_u32(&[0, 0, 1, 2, 3], 1), Some(0x0001_0203));
+/// assert_eq!(read_u32(&[0, 0, 1, 2, 3], 2), None);
+/// ```
+#[macro_export]
+macro_rules! try_window {
+    ($slice: expr, $start: expr, $len: expr) => {
+        $crate::try_window!($slice, $start, $len, ())
+    };
+
+    ($slice: expr, $start: expr, $len: expr, $ret: expr) => {
+        match $crate::__window::<_, { $len }>($slice, $start) {
+            ::core::option::Option::Some(v) => v,
+            ::core::option::Option::None => return $ret,
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    const MAGIC_LEN: usize = 2;
+
+    #[derive(Debug, PartialEq)]
+    struct Header {
+        magic: [u8; MAGIC_LEN],
+        len: u16,
+        id: u32,
+    }
+
+    fn header(buf: &[u8]) -> Result<Header, &'static str> {
+        let magic = try_window!(buf, 0, MAGIC_LEN, Err("short magic"));
+        let len = try_window!(buf, MAGIC_LEN, 2, Err("short len"));
+        let id = try_window!(buf, MAGIC_LEN + 2, 4, Err("short id"));
+        Ok(Header {
+            magic: *magic,
+            len: u16::from_le_bytes(*len),
+            id: u32::from_le_bytes(*id),
+        })
+    }
+
+    #[test]
+    fn try_window_bounds() {
+        let buf = [b'T', b'U', 1, 0, 7, 0, 0, 0, 0xff];
+        assert_eq!(
+            header(&buf),
+            Ok(Header {
+                magic: *b"TU",
+                len: 1,
+                id: 7,
+            })
+        );
+        assert_eq!(header(&buf[..8]).unwrap().id, 7);
+        assert_eq!(header(&buf[..7]), Err("short id"));
+        assert_eq!(header(&buf[..3]), Err("short len"));
+        assert_eq!(header(&buf[..1]), Err("short magic"));
+        assert_eq!(header(&[]), Err("short magic"));
+    }
+
+    #[test]
+    fn try_window_edges() {
+        fn window(words: &[&'static str], start: usize) -> Option<[&'static str; 2]> {
+            Some(*try_window!(words, start, 2, None))
+        }
+
+        fn empty(words: &[u8], start: usize) -> bool {
+            let w: &[u8; 0] = try_window!(words, start, 0, false);
+            w.is_empty()
+        }
+
+        fn unit(words: &[u8]) {
+            let _ = try_window!(words, usize::MAX, 1);
+            panic!();
+        }
+
+        let words = vec!["a", "b", "c"];
+        assert_eq!(window(&words, 1), Some(["b", "c"]));
+        assert_eq!(window(&words, 2), None);
+        assert_eq!(window(&words, usize::MAX), None);
+        assert!(empty(&[1], 1));
+        assert!(!empty(&[1], 2));
+        unit(&[1]);
+    }
+}
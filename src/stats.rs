@@ -0,0 +1,125 @@
+/// Counts of what happened to the items of a processing loop, updated by
+/// `try_continue!(expr, stats: &mut stats)`
+///
+/// ```
+/// use try_utils::{try_continue, Stats};
+///
+/// let mut stats = Stats::default();
+/// for s in ["1", "x", "", "2"] {
+///     let val = try_continue!(s.parse::<u32>(), stats: &mut stats);
+///     assert!(val > 0);
+/// }
+/// assert_eq!(stats.processed, 2);
+/// assert_eq!(stats.errored, 2);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Stats {
+    /// Items that were `Some` or `Ok`
+    pub processed: usize,
+    /// Items that were `None`
+    pub skipped: usize,
+    /// Items that were `Err`
+    pub errored: usize,
+}
+
+impl Stats {
+    /// Returns the number of items counted
+    pub fn total(&self) -> usize {
+        self.processed + self.skipped + self.errored
+    }
+}
+
+/// A trait for types that can count themselves in [`Stats`], telling `None`
+/// apart from `Err`
+#[doc(hidden)]
+pub trait __Tally {
+    type Output;
+    fn tally(self, stats: &mut Stats) -> Option<Self::Output>;
+}
+
+impl<T> __Tally for Option<T> {
+    type Output = T;
+
+    fn tally(self, stats: &mut Stats) -> Option<T> {
+        match self {
+            Some(v) => {
+                stats.processed += 1;
+                Some(v)
+            }
+            None => {
+                stats.skipped += 1;
+                None
+            }
+        }
+    }
+}
+
+impl<T, E> __Tally for Result<T, E> {
+    type Output = T;
+
+    fn tally(self, stats: &mut Stats) -> Option<T> {
+        match self {
+            Ok(v) => {
+                stats.processed += 1;
+                Some(v)
+            }
+            Err(_) => {
+                stats.errored += 1;
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::try_continue;
+
+    #[test]
+    fn try_continue_stats() {
+        let fields = [
+            Some("1"),
+            None,
+            Some("x"),
+            Some("2"),
+            Some("-"),
+            None,
+            None,
+            Some("3"),
+        ];
+
+        let mut stats = Stats::default();
+        let mut sum = 0;
+        for field in fields {
+            let val: u32 = match field {
+                Some(s) => try_continue!(s.parse(), stats: &mut stats),
+                None => try_continue!(None, stats: &mut stats),
+            };
+            sum += val;
+        }
+        assert_eq!(sum, 6);
+        assert_eq!(
+            stats,
+            Stats {
+                processed: 3,
+                skipped: 3,
+                errored: 2,
+            }
+        );
+        assert_eq!(stats.total(), fields.len());
+    }
+
+    #[test]
+    fn try_continue_stats_label() {
+        let mut stats = Stats::default();
+        'outer: for row in [["1", "x", "2"], ["3", "4", "5"]] {
+            for s in row {
+                let _: u32 = try_continue!(s.parse(), stats: &mut stats, 'outer);
+            }
+        }
+        assert_eq!(stats.processed, 4);
+        assert_eq!(stats.skipped, 0);
+        assert_eq!(stats.errored, 1);
+    }
+}
@@ -0,0 +1,322 @@
+use std::sync::LockResult;
+
+use crate::TryAsOption;
+
+#[doc(hidden)]
+pub fn __lock_recover<G>(res: LockResult<G>, recover: impl FnOnce(&mut G)) -> G {
+    match res {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            let mut guard = poisoned.into_inner();
+            recover(&mut guard);
+            guard
+        }
+    }
+}
+
+#[doc(hidden)]
+pub fn __unlocked<G, R: TryAsOption>(
+    res: LockResult<G>,
+    read: impl FnOnce(&mut G) -> R,
+) -> Option<R::Output> {
+    let mut guard = res.ok()?;
+    let out = read(&mut guard);
+    drop(guard);
+    out.try_as_option()
+}
+
+#[doc(hidden)]
+pub fn __read_released<G, R>(res: LockResult<G>, read: impl FnOnce(&G) -> R) -> Option<R> {
+    let guard = res.ok()?;
+    Some(read(&guard))
+}
+
+/// Returns the guard of a lock result, running a recovery callback on it first
+/// if the lock was poisoned.
+///
+/// The callback receives the guard mutably so it can put the protected state
+/// back into a consistent state. The lock stays poisoned; this only changes
+/// what happens at this call site. Any `LockResult` can be used, such as the
+/// results of `Mutex::lock` and `RwLock::write`.
+///
+/// ```
+/// use std::sync::Mutex;
+/// use try_utils::try_lock_recover;
+///
+/// let mutex = Mutex::new(vec![1, 2, 3]);
+/// let guard = try_lock_recover!(mutex.lock(), |guard| guard.clear());
+/// assert_eq!(*guard, vec![1, 2, 3]);
+/// ```
+#[macro_export]
+macro_rules! try_lock_recover {
+    ($e: expr, $recover: expr) => {
+        $crate::__lock_recover($e, $recover)
+    };
+}
+
+/// Runs a closure with the guard of a lock result and returns the value of
+/// its result if it is `Some` or `Ok`, otherwise returns from the current
+/// function with the given value or `()` if none is given.
+///
+/// The guard is dropped before returning, so the lock isn't held while the
+/// return value is evaluated. A poisoned lock also returns, without running
+/// the closure. The closure can't return anything borrowing from the guard.
+///
+/// ```
+/// use std::sync::Mutex;
+/// use try_utils::try_return_unlocked;
+///
+/// fn next_job(queue: &Mutex<Vec<u32>>) -> u32 {
+///     let job = try_return_unlocked!(queue.lock(), |queue| queue.pop(), 0);
+///     job
+/// }
+///
+/// let queue = Mutex::new(vec![1]);
+/// assert_eq!(next_job(&queue), 1);
+/// assert_eq!(next_job(&queue), 0);
+/// ```
+#[macro_export]
+macro_rules! try_return_unlocked {
+    ($e: expr, $read: expr) => {
+        $crate::try_return_unlocked!($e, $read, ())
+    };
+
+    ($e: expr, $read: expr, $ret: expr) => {
+        match $crate::__unlocked($e, $read) {
+            ::core::option::Option::Some(v) => v,
+            ::core::option::Option::None => return $ret,
+        }
+    };
+}
+
+/// Runs a closure with the guard of a lock result and returns what it
+/// extracted, otherwise returns from the current function with the given
+/// value or `()` if none is given if the lock was poisoned.
+///
+/// The guard is released as soon as the closure returns, so the closure
+/// should copy or clone what it needs out of the guarded value. It is meant
+/// for `RwLock::read`, but any `LockResult` can be used.
+///
+/// ```
+/// use std::sync::RwLock;
+/// use try_utils::try_rwlock_read;
+///
+/// fn name(config: &RwLock<(String, u16)>) -> Option<String> {
+///     let name = try_rwlock_read!(config.read(), |config| config.0.clone(), None);
+///     Some(name)
+/// }
+/// assert_eq!(name(&RwLock::new(("app".to_owned(), 80))).unwrap(), "app");
+/// ```
+#[macro_export]
+macro_rules! try_rwlock_read {
+    ($e: expr, $read: expr) => {
+        $crate::try_rwlock_read!($e, $read, ())
+    };
+
+    ($e: expr, $read: expr, $ret: expr) => {
+        match $crate::__read_released($e, $read) {
+            ::core::option::Option::Some(v) => v,
+            ::core::option::Option::None => return $ret,
+        }
+    };
+}
+
+/// Sends the value of an expression to one channel if it is `Some` or `Ok`,
+/// or the error to another if it is `Err` (`()` for `None`), then continues
+/// the current loop.
+///
+/// Works with any senders with a `send` method, like those from
+/// `std::sync::mpsc`. A value sent to a closed channel is dropped. A label
+/// can be given to continue a loop with a label, or `return` with a value to
+/// return from the current function instead.
+///
+/// ```
+/// use std::sync::mpsc;
+/// use try_utils::try_route;
+///
+/// let (ok_tx, ok_rx) = mpsc::channel();
+/// let (err_tx, err_rx) = mpsc::channel();
+/// for val in ["1", "x", "3"] {
+///     try_route!(val.parse::<u32>(), ok_tx, err_tx);
+/// }
+/// drop((ok_tx, err_tx));
+/// assert_eq!(ok_rx.iter().collect::<Vec<_>>(), vec![1, 3]);
+/// assert_eq!(err_rx.iter().count(), 1);
+/// ```
+#[macro_export]
+macro_rules! try_route {
+    (@send $e: expr, $ok_tx: expr, $err_tx: expr) => {{
+        use $crate::TryAsResult;
+        match $e.try_as_result() {
+            Ok(v) => {
+                let _ = $ok_tx.send(v);
+            }
+            Err(e) => {
+                let _ = $err_tx.send(e);
+            }
+        }
+    }};
+
+    ($e: expr, $ok_tx: expr, $err_tx: expr) => {{
+        $crate::try_route!(@send $e, $ok_tx, $err_tx);
+        continue;
+    }};
+
+    ($e: expr, $ok_tx: expr, $err_tx: expr, $label: lifetime) => {{
+        $crate::try_route!(@send $e, $ok_tx, $err_tx);
+        continue $label;
+    }};
+
+    ($e: expr, $ok_tx: expr, $err_tx: expr, return $ret: expr) => {{
+        $crate::try_route!(@send $e, $ok_tx, $err_tx);
+        return $ret;
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{mpsc, Mutex, RwLock, TryLockError};
+    use std::thread;
+
+    fn poison<T: Send>(mutex: &Mutex<T>) {
+        thread::scope(|s| {
+            let res = s
+                .spawn(|| {
+                    let _guard = mutex.lock().unwrap();
+                    panic!("poison the mutex");
+                })
+                .join();
+            assert!(res.is_err());
+        });
+        assert!(mutex.is_poisoned());
+    }
+
+    #[test]
+    fn try_lock_recover_poisoned() {
+        let mutex = Mutex::new(vec![1, 2, 3]);
+        poison(&mutex);
+
+        let mut recovered = false;
+        let guard = try_lock_recover!(mutex.lock(), |guard| {
+            recovered = true;
+            guard.clear();
+        });
+        assert!(guard.is_empty());
+        drop(guard);
+        assert!(recovered);
+    }
+
+    #[test]
+    fn try_lock_recover_healthy() {
+        let mutex = Mutex::new(vec![1, 2, 3]);
+        let guard = try_lock_recover!(mutex.lock(), |_| panic!());
+        assert_eq!(*guard, vec![1, 2, 3]);
+
+        let lock = RwLock::new(10);
+        *try_lock_recover!(lock.write(), |_| panic!()) += 1;
+        assert_eq!(*lock.read().unwrap(), 11);
+    }
+
+    #[test]
+    fn try_route_drains() {
+        let (ok_tx, ok_rx) = mpsc::channel();
+        let (err_tx, err_rx) = mpsc::channel();
+        let mut count = 0;
+        for val in [Ok(1), Err("a"), Ok(2), Err("b")] {
+            count += 1;
+            try_route!(val, ok_tx, err_tx);
+        }
+        drop((ok_tx, err_tx));
+        assert_eq!(count, 4);
+        assert_eq!(ok_rx.iter().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(err_rx.iter().collect::<Vec<_>>(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn try_route_closed_and_return() {
+        fn route(val: Option<u32>, ok_tx: &mpsc::Sender<u32>, err_tx: &mpsc::Sender<()>) -> bool {
+            try_route!(val, ok_tx, err_tx, return true);
+        }
+
+        let (ok_tx, ok_rx) = mpsc::channel();
+        let (err_tx, err_rx) = mpsc::channel();
+        assert!(route(Some(1), &ok_tx, &err_tx));
+        assert!(route(None, &ok_tx, &err_tx));
+        assert_eq!(ok_rx.try_recv(), Ok(1));
+        assert_eq!(err_rx.try_recv(), Ok(()));
+
+        drop(ok_rx);
+        drop(err_rx);
+        assert!(route(Some(2), &ok_tx, &err_tx));
+        assert!(route(None, &ok_tx, &err_tx));
+    }
+
+    #[test]
+    fn try_return_unlocked_releases() {
+        fn pop(queue: &Mutex<Vec<u32>>) -> Option<u32> {
+            let val = try_return_unlocked!(queue.lock(), |queue| queue.pop(), {
+                // the guard must already be dropped here
+                assert!(!matches!(queue.try_lock(), Err(TryLockError::WouldBlock)));
+                None
+            });
+            Some(val)
+        }
+
+        fn first_even(nums: &RwLock<Vec<u32>>, out: &mut Vec<u32>) {
+            let val = try_return_unlocked!(nums.read(), |nums| {
+                nums.iter().copied().find(|n| n % 2 == 0).ok_or(())
+            });
+            out.push(val);
+        }
+
+        let queue = Mutex::new(vec![1, 2]);
+        assert_eq!(pop(&queue), Some(2));
+        assert_eq!(pop(&queue), Some(1));
+        assert_eq!(pop(&queue), None);
+        assert!(queue.try_lock().is_ok());
+
+        poison(&queue);
+        assert_eq!(pop(&queue), None);
+
+        let mut out = Vec::new();
+        first_even(&RwLock::new(vec![1, 4]), &mut out);
+        first_even(&RwLock::new(vec![1, 3]), &mut out);
+        assert_eq!(out, vec![4]);
+    }
+
+    #[test]
+    fn try_rwlock_read_poisoned() {
+        fn port(config: &RwLock<(String, u16)>) -> Result<u16, &'static str> {
+            let port = try_rwlock_read!(config.read(), |config| config.1, {
+                // the read guard must already be released here
+                assert!(!matches!(config.try_write(), Err(TryLockError::WouldBlock)));
+                Err("poisoned")
+            });
+            Ok(port)
+        }
+
+        let config = RwLock::new(("app".to_owned(), 8080));
+        assert_eq!(port(&config), Ok(8080));
+        assert!(config.try_write().is_ok());
+
+        thread::scope(|s| {
+            let res = s
+                .spawn(|| {
+                    let _guard = config.write().unwrap();
+                    panic!("poison the lock");
+                })
+                .join();
+            assert!(res.is_err());
+        });
+        assert!(config.is_poisoned());
+        assert_eq!(port(&config), Err("poisoned"));
+
+        let mut names = Vec::new();
+        let mut read_name = |lock: &RwLock<(String, u16)>| {
+            names.push(try_rwlock_read!(lock.read(), |config| config.0.clone()));
+        };
+        read_name(&RwLock::new(("a".to_owned(), 1)));
+        read_name(&config);
+        assert_eq!(names, vec!["a"]);
+    }
+}
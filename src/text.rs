@@ -0,0 +1,251 @@
+use core::str::FromStr;
+
+#[doc(hidden)]
+pub fn __utf8_prefix(bytes: &[u8]) -> Option<&str> {
+    match core::str::from_utf8(bytes) {
+        Ok(s) => Some(s),
+        Err(e) if e.valid_up_to() == 0 => None,
+        Err(e) => core::str::from_utf8(&bytes[..e.valid_up_to()]).ok(),
+    }
+}
+
+/// Parses a `&str`, on failure pairing the error with the byte offset given
+/// by `pos`
+#[doc(hidden)]
+pub fn __parse_pos<T: FromStr>(
+    s: &str,
+    pos: impl FnOnce(&str, &T::Err) -> usize,
+) -> Result<T, (usize, T::Err)> {
+    s.parse().map_err(|e| (pos(s, &e), e))
+}
+
+/// Returns the length of the longest proper prefix of `s` that parses
+#[doc(hidden)]
+pub fn __longest_prefix<T: FromStr>(s: &str, _: &T::Err) -> usize {
+    s.char_indices()
+        .rev()
+        .map(|(i, _)| i)
+        .find(|&i| i > 0 && s[..i].parse::<T>().is_ok())
+        .unwrap_or(0)
+}
+
+/// Splits a `&str` at the first occurrence of a delimiter, yielding the parts
+/// before and after it, otherwise returns from the current function with the
+/// given value or `()` if none is given when the delimiter is absent.
+///
+/// The delimiter can be anything `str::split_once` accepts, like a `char` or
+/// a `&str`.
+///
+/// ```
+/// use try_utils::try_until_delim;
+///
+/// fn parse(line: &str) -> Option<(&str, &str)> {
+///     let (key, value) = try_until_delim!(line, ':', None);
+///     Some((key.trim(), value.trim()))
+/// }
+/// assert_eq!(parse("host: example.com"), Some(("host", "example.com")));
+/// assert_eq!(parse("no delimiter"), None);
+/// ```
+#[macro_export]
+macro_rules! try_until_delim {
+    ($s: expr, $delim: expr) => {
+        $crate::try_until_delim!($s, $delim, ())
+    };
+
+    ($s: expr, $delim: expr, $ret: expr) => {
+        match ::core::primitive::str::split_once($s, $delim) {
+            ::core::option::Option::Some(parts) => parts,
+            ::core::option::Option::None => return $ret,
+        }
+    };
+}
+
+/// Returns the longest valid UTF-8 prefix of a byte slice as a `&str`,
+/// otherwise returns from the current function with the given value or `()`
+/// if none is given when the very first byte is invalid.
+///
+/// In streaming decoders the rest of the input, starting at the returned
+/// prefix's length, can be kept for when more bytes arrive. An empty slice
+/// yields an empty `&str`.
+///
+/// Only an invalid or incomplete sequence after at least one valid character
+/// is cut off. A slice that starts with one, like a buffer split partway
+/// through a multibyte character, has no valid prefix and fails even if
+/// complete characters follow it.
+///
+/// ```
+/// use try_utils::try_utf8_prefix;
+///
+/// fn decode(bytes: &[u8]) -> Option<&str> {
+///     let s = try_utf8_prefix!(bytes, None);
+///     Some(s)
+/// }
+/// assert_eq!(decode(b"hello"), Some("hello"));
+/// assert_eq!(decode(b"caf\xc3"), Some("caf"));
+/// assert_eq!(decode(b"\xffabc"), None);
+/// assert_eq!(decode(&"é!".as_bytes()[1..]), None);
+/// ```
+#[macro_export]
+macro_rules! try_utf8_prefix {
+    ($bytes: expr) => {
+        $crate::try_utf8_prefix!($bytes, ())
+    };
+
+    ($bytes: expr, $ret: expr) => {
+        match $crate::__utf8_prefix($bytes) {
+            ::core::option::Option::Some(s) => s,
+            ::core::option::Option::None => return $ret,
+        }
+    };
+}
+
+/// Parses a `&str` into the given type with `FromStr`, otherwise returns
+/// from the current function with the result of a closure called with the
+/// byte offset of the error and the error.
+///
+/// A `pos:` function can be given to find the offset from the input and the
+/// error, for types whose errors carry a position. Otherwise the offset is
+/// found by re-parsing, and is the length of the longest prefix of the input
+/// that parses, or `0` if none does.
+///
+/// ```
+/// use try_utils::try_parse_pos;
+///
+/// fn parse(s: &str) -> Result<u32, String> {
+///     let val = try_parse_pos!(s => u32, |offset, _| Err(format!("bad digit at {}", offset)));
+///     Ok(val)
+/// }
+/// assert_eq!(parse("123"), Ok(123));
+/// assert_eq!(parse("12x4"), Err("bad digit at 2".to_owned()));
+/// ```
+#[macro_export]
+macro_rules! try_parse_pos {
+    ($s: expr => $ty: ty, pos: $pos: expr, $ret: expr) => {
+        match $crate::__parse_pos::<$ty>($s, $pos) {
+            ::core::result::Result::Ok(v) => v,
+            ::core::result::Result::Err((offset, e)) => return $crate::__call2(offset, e, $ret),
+        }
+    };
+
+    ($s: expr => $ty: ty, $ret: expr) => {
+        match $crate::__parse_pos::<$ty>($s, $crate::__longest_prefix::<$ty>) {
+            ::core::result::Result::Ok(v) => v,
+            ::core::result::Result::Err((offset, e)) => return $crate::__call2(offset, e, $ret),
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::try_continue;
+
+    #[test]
+    fn try_until_delim_present() {
+        fn split(s: &str) -> (&str, &str) {
+            try_until_delim!(s, "=>", ("", ""))
+        }
+
+        assert_eq!(split("a=>b=>c"), ("a", "b=>c"));
+        assert_eq!(split("=>"), ("", ""));
+        assert_eq!(split("a=b"), ("", ""));
+    }
+
+    #[test]
+    fn try_until_delim_key_values() {
+        fn parse(input: &str) -> HashMap<&str, &str> {
+            let mut out = HashMap::new();
+            for line in input.lines() {
+                let (key, value) = try_continue!(line.split_once(':'));
+                out.insert(key, value);
+            }
+            out
+        }
+
+        fn first(input: &str, out: &mut Vec<String>) {
+            let (key, _) = try_until_delim!(input, ':');
+            out.push(key.to_owned());
+        }
+
+        let parsed = parse("a:1\nbad\nb:2");
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed["b"], "2");
+
+        let mut out = Vec::new();
+        first("k:v", &mut out);
+        first("kv", &mut out);
+        assert_eq!(out, vec!["k"]);
+    }
+
+    #[test]
+    fn try_utf8_prefix_inputs() {
+        fn decode(bytes: &[u8]) -> Result<(&str, usize), ()> {
+            let s = try_utf8_prefix!(bytes, Err(()));
+            Ok((s, bytes.len() - s.len()))
+        }
+
+        assert_eq!(decode("héllo".as_bytes()), Ok(("héllo", 0)));
+        assert_eq!(decode(b""), Ok(("", 0)));
+
+        let snowman = "a☃".as_bytes();
+        assert_eq!(decode(&snowman[..3]), Ok(("a", 2)));
+        assert_eq!(decode(b"ab\xffcd"), Ok(("ab", 3)));
+
+        assert_eq!(decode(b"\xff"), Err(()));
+        assert_eq!(decode(&snowman[1..3]), Err(()));
+
+        // complete characters after a leading partial one aren't a prefix
+        let split = &"☃bc".as_bytes()[1..];
+        assert_eq!(decode(split), Err(()));
+        assert_eq!(decode(&split[2..]), Ok(("bc", 0)));
+        assert_eq!(decode(&"☃bc".as_bytes()[..2]), Err(()));
+    }
+
+    #[test]
+    fn try_parse_pos_reparse() {
+        fn parse<T: std::str::FromStr>(s: &str) -> Result<T, usize> {
+            let val = try_parse_pos!(s => T, |offset, _| Err(offset));
+            Ok(val)
+        }
+
+        assert_eq!(parse::<i32>("-42"), Ok(-42));
+        assert_eq!(parse::<i32>("12x4"), Err(2));
+        assert_eq!(parse::<i32>("-"), Err(0));
+        assert_eq!(parse::<i32>(""), Err(0));
+        assert_eq!(parse::<f64>("1.5.2"), Err(3));
+        assert_eq!(parse::<u8>("25é"), Err(2));
+        assert_eq!(parse::<u8>("2556"), Err(3));
+    }
+
+    #[test]
+    fn try_parse_pos_custom() {
+        #[derive(Debug, PartialEq)]
+        struct Pair(u32, u32);
+
+        #[derive(Debug)]
+        struct PairError(usize);
+
+        impl std::str::FromStr for Pair {
+            type Err = PairError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let (a, b) = s.split_once(',').ok_or(PairError(s.len()))?;
+                let a = a.parse().map_err(|_| PairError(0))?;
+                let b = b.parse().map_err(|_| PairError(s.len() - b.len()))?;
+                Ok(Pair(a, b))
+            }
+        }
+
+        fn parse(s: &str) -> Result<Pair, (usize, String)> {
+            let pair = try_parse_pos!(s => Pair, pos: |_, e| e.0, |offset, e: PairError| {
+                Err((offset, format!("{:?}", e)))
+            });
+            Ok(pair)
+        }
+
+        assert_eq!(parse("1,2"), Ok(Pair(1, 2)));
+        assert_eq!(parse("1,x"), Err((2, "PairError(2)".to_owned())));
+        assert_eq!(parse("12"), Err((2, "PairError(2)".to_owned())));
+    }
+}
@@ -0,0 +1,224 @@
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+
+/// A timed guard recorded by [`try_span_timed!`](crate::try_span_timed)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimingNode {
+    /// The name given to the guard
+    pub name: &'static str,
+    /// How long the guard took to resolve, including its children
+    pub duration: Duration,
+    /// Timed guards that resolved while this one was running, in order
+    pub children: Vec<TimingNode>,
+}
+
+struct Open {
+    name: &'static str,
+    start: Instant,
+    children: Vec<TimingNode>,
+}
+
+#[derive(Default)]
+struct Timings {
+    open: Vec<Open>,
+    roots: Vec<TimingNode>,
+}
+
+thread_local! {
+    static TIMINGS: RefCell<Timings> = RefCell::new(Timings::default());
+}
+
+/// Returns the timed guards that resolved on the current thread, outermost
+/// first, each with the guards nested inside it as children.
+///
+/// Guards that are still running aren't included.
+///
+/// ```
+/// use try_utils::try_span_timed;
+///
+/// fn load(val: Option<i32>) -> i32 {
+///     let val = try_span_timed!(val, "load", 1234);
+///     val
+/// }
+/// load(None);
+///
+/// let tree = try_utils::timing_tree();
+/// assert_eq!(tree.last().unwrap().name, "load");
+/// ```
+pub fn timing_tree() -> Vec<TimingNode> {
+    TIMINGS.with(|timings| timings.borrow().roots.clone())
+}
+
+/// Removes all resolved timed guards on the current thread
+pub fn clear_timing_tree() {
+    TIMINGS.with(|timings| timings.borrow_mut().roots.clear());
+}
+
+/// Pushes a frame onto the current thread's timing stack, popping it and
+/// recording its duration when dropped
+///
+/// Frames must be dropped in reverse order on the thread that entered them,
+/// so they are neither `Send` nor `Sync`.
+#[doc(hidden)]
+pub struct __TimingFrame(PhantomData<*const ()>);
+
+impl __TimingFrame {
+    pub fn enter(name: &'static str) -> Self {
+        TIMINGS.with(|timings| {
+            timings.borrow_mut().open.push(Open {
+                name,
+                start: Instant::now(),
+                children: Vec::new(),
+            })
+        });
+        __TimingFrame(PhantomData)
+    }
+}
+
+impl Drop for __TimingFrame {
+    fn drop(&mut self) {
+        TIMINGS.with(|timings| {
+            let mut timings = timings.borrow_mut();
+            // frames are dropped in the reverse order they were entered
+            let Some(open) = timings.open.pop() else {
+                return;
+            };
+            let node = TimingNode {
+                name: open.name,
+                duration: open.start.elapsed(),
+                children: open.children,
+            };
+            match timings.open.last_mut() {
+                Some(parent) => parent.children.push(node),
+                None => timings.roots.push(node),
+            }
+        });
+    }
+}
+
+/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
+/// returns from the current function with the given value or `()` if none is
+/// given, timing how long the guard took to resolve.
+///
+/// The time is recorded in a per thread tree read with [`timing_tree`], with
+/// timed guards that resolve while evaluating the expression as children.
+///
+/// ```
+/// use try_utils::try_span_timed;
+///
+/// fn parse(s: &str) -> u32 {
+///     try_span_timed!(s.parse::<u32>(), "parse", 0)
+/// }
+///
+/// fn load(s: Option<&str>) -> u32 {
+///     try_span_timed!(s.map(parse), "load", 0)
+/// }
+/// load(Some("10"));
+///
+/// let tree = try_utils::timing_tree();
+/// let load = tree.last().unwrap();
+/// assert_eq!(load.name, "load");
+/// assert_eq!(load.children[0].name, "parse");
+/// ```
+///
+/// [`timing_tree`]: crate::timing_tree
+#[macro_export]
+macro_rules! try_span_timed {
+    ($e: expr, $name: expr) => {
+        $crate::try_span_timed!($e, $name, ())
+    };
+
+    ($e: expr, $name: expr, $ret: expr) => {{
+        use $crate::TryAsOption;
+        let _frame = $crate::__TimingFrame::enter($name);
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => return $ret,
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    fn sleep_ms(ms: u64) -> Option<u64> {
+        thread::sleep(Duration::from_millis(ms));
+        Some(ms)
+    }
+
+    fn child(ms: u64) -> Option<u64> {
+        Some(try_span_timed!(sleep_ms(ms), "child", None))
+    }
+
+    fn parent(fail: bool) -> Option<u64> {
+        let total = try_span_timed!(child(5).zip(child(10)).filter(|_| !fail), "parent", None);
+        Some(total.0 + total.1)
+    }
+
+    #[test]
+    fn try_span_timed_nested() {
+        clear_timing_tree();
+        assert_eq!(parent(false), Some(15));
+        assert_eq!(parent(true), None);
+
+        let tree = timing_tree();
+        assert_eq!(tree.len(), 2);
+        for parent in &tree {
+            assert_eq!(parent.name, "parent");
+            let names = parent.children.iter().map(|c| c.name).collect::<Vec<_>>();
+            assert_eq!(names, vec!["child", "child"]);
+            assert!(parent.children.iter().all(|c| c.children.is_empty()));
+
+            let first = parent.children[0].duration;
+            let second = parent.children[1].duration;
+            assert!(first >= Duration::from_millis(5));
+            assert!(second >= Duration::from_millis(10));
+            assert!(parent.duration >= first + second);
+        }
+
+        clear_timing_tree();
+        assert!(timing_tree().is_empty());
+    }
+
+    #[test]
+    fn try_span_timed_per_thread() {
+        clear_timing_tree();
+        let _ = child(1);
+        thread::spawn(|| {
+            assert!(timing_tree().is_empty());
+            let _ = child(1);
+            assert_eq!(timing_tree().len(), 1);
+        })
+        .join()
+        .unwrap();
+        assert_eq!(timing_tree().len(), 1);
+    }
+
+    #[test]
+    fn timing_frames_pop_in_order() {
+        clear_timing_tree();
+        {
+            let _outer = __TimingFrame::enter("outer");
+            {
+                let _middle = __TimingFrame::enter("middle");
+                let _inner = __TimingFrame::enter("inner");
+            }
+            let _sibling = __TimingFrame::enter("sibling");
+        }
+
+        let tree = timing_tree();
+        assert_eq!(tree.len(), 1);
+        let outer = &tree[0];
+        assert_eq!(outer.name, "outer");
+        let names = outer.children.iter().map(|c| c.name).collect::<Vec<_>>();
+        assert_eq!(names, ["middle", "sibling"]);
+        assert_eq!(outer.children[0].children.len(), 1);
+        assert_eq!(outer.children[0].children[0].name, "inner");
+        assert!(outer.children[1].children.is_empty());
+        clear_timing_tree();
+    }
+}
@@ -0,0 +1,245 @@
+use std::time::Instant;
+
+#[doc(hidden)]
+pub use tracing as __tracing;
+use tracing::span::EnteredSpan;
+
+/// Keeps a span entered and records how long it was entered for when
+/// dropped, before the span is exited and closed.
+#[doc(hidden)]
+pub struct __TimedSpan {
+    span: EnteredSpan,
+    start: Instant,
+}
+
+impl __TimedSpan {
+    pub fn enter(span: tracing::Span) -> Self {
+        __TimedSpan {
+            span: span.entered(),
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for __TimedSpan {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed().as_micros() as u64;
+        self.span.record("elapsed_us", elapsed);
+    }
+}
+
+/// Returns the value of an expression if it is `Some` or `Ok`, otherwise
+/// returns from the current function with the given value or `()` if none is
+/// given, evaluating the expression inside a span with the given name.
+///
+/// The span has an `elapsed_us` field which is recorded with the time in
+/// microseconds until the guard resolved, whether it succeeded or returned.
+/// The name must be a string literal.
+///
+/// ```
+/// use try_utils::try_return_timed_span;
+///
+/// fn load(val: Option<i32>) -> i32 {
+///     let val = try_return_timed_span!(val, "load", 1234);
+///     val
+/// }
+/// assert_eq!(load(Some(10)), 10);
+/// assert_eq!(load(None), 1234);
+/// ```
+#[macro_export]
+macro_rules! try_return_timed_span {
+    ($e: expr, $name: literal) => {
+        $crate::try_return_timed_span!($e, $name, ())
+    };
+
+    ($e: expr, $name: literal, $ret: expr) => {{
+        use $crate::TryAsOption;
+        let _timed = $crate::__TimedSpan::enter($crate::__tracing::info_span!(
+            $name,
+            elapsed_us = $crate::__tracing::field::Empty
+        ));
+        match $e.try_as_option() {
+            Some(v) => v,
+            None => return $ret,
+        }
+    }};
+}
+
+#[cfg(test)]
+pub(crate) mod test_subscriber {
+    use std::fmt;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// Something the subscriber saw, with its fields formatted with `Debug`
+    #[derive(Debug, Clone, PartialEq)]
+    pub(crate) enum Seen {
+        Event(Vec<(String, String)>),
+        NewSpan(String, Vec<(String, String)>),
+        Record(Vec<(String, String)>),
+        Close(String),
+    }
+
+    struct Fields(Vec<(String, String)>);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0
+                .push((field.name().to_owned(), format!("{:?}", value)));
+        }
+    }
+
+    /// A subscriber recording everything it sees
+    #[derive(Clone, Default)]
+    pub(crate) struct Capture {
+        seen: Arc<Mutex<Vec<Seen>>>,
+        names: Arc<Mutex<Vec<String>>>,
+        next_id: Arc<AtomicU64>,
+    }
+
+    impl Capture {
+        pub(crate) fn seen(&self) -> Vec<Seen> {
+            self.seen.lock().unwrap().clone()
+        }
+
+        fn push(&self, seen: Seen) {
+            self.seen.lock().unwrap().push(seen);
+        }
+
+        fn name(&self, id: &Id) -> String {
+            self.names.lock().unwrap()[id.into_u64() as usize - 1].clone()
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut fields = Fields(Vec::new());
+            span.record(&mut fields);
+            let name = span.metadata().name().to_owned();
+            self.names.lock().unwrap().push(name.clone());
+            self.push(Seen::NewSpan(name, fields.0));
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::SeqCst) + 1)
+        }
+
+        fn record(&self, _: &Id, values: &Record<'_>) {
+            let mut fields = Fields(Vec::new());
+            values.record(&mut fields);
+            self.push(Seen::Record(fields.0));
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields(Vec::new());
+            event.record(&mut fields);
+            self.push(Seen::Event(fields.0));
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+
+        fn try_close(&self, id: Id) -> bool {
+            self.push(Seen::Close(self.name(&id)));
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_subscriber::{Capture, Seen};
+    use crate::try_continue;
+
+    fn field(name: &str, value: &str) -> (String, String) {
+        (name.to_owned(), value.to_owned())
+    }
+
+    #[test]
+    fn try_continue_iter_event() {
+        let capture = Capture::default();
+        let mut kept = Vec::new();
+        tracing::subscriber::with_default(capture.clone(), || {
+            for (i, val) in [Some(1), None, Some(3), None].into_iter().enumerate() {
+                kept.push(try_continue!(val, iter = i, "missing value"));
+            }
+        });
+        assert_eq!(kept, vec![1, 3]);
+
+        let events = capture
+            .seen()
+            .into_iter()
+            .map(|seen| match seen {
+                Seen::Event(fields) => fields,
+                seen => panic!("unexpected {:?}", seen),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                vec![
+                    field("message", "missing value"),
+                    field("iter", "1"),
+                    field("guard", "\"val\"")
+                ],
+                vec![
+                    field("message", "missing value"),
+                    field("iter", "3"),
+                    field("guard", "\"val\"")
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn try_continue_iter_event_label() {
+        let capture = Capture::default();
+        let mut count = 0;
+        tracing::subscriber::with_default(capture.clone(), || {
+            'outer: for i in 0..3 {
+                count += 1;
+                loop {
+                    let _: u32 = try_continue!(None, iter = i, "inner", 'outer);
+                }
+            }
+        });
+        assert_eq!(count, 3);
+        assert_eq!(capture.seen().len(), 3);
+    }
+
+    #[test]
+    fn try_return_timed_span_both_paths() {
+        fn load(val: Option<u32>) -> u32 {
+            try_return_timed_span!(val, "load", 0)
+        }
+
+        let capture = Capture::default();
+        tracing::subscriber::with_default(capture.clone(), || {
+            assert_eq!(load(Some(1)), 1);
+            assert_eq!(load(None), 0);
+        });
+
+        let seen = capture.seen();
+        assert_eq!(seen.len(), 6);
+        for path in seen.chunks(3) {
+            assert_eq!(path[0], Seen::NewSpan("load".to_owned(), vec![]));
+            match &path[1] {
+                Seen::Record(fields) => {
+                    assert_eq!(fields.len(), 1);
+                    assert_eq!(fields[0].0, "elapsed_us");
+                    fields[0].1.parse::<u64>().unwrap();
+                }
+                seen => panic!("unexpected {:?}", seen),
+            }
+            assert_eq!(path[2], Seen::Close("load".to_owned()));
+        }
+    }
+}
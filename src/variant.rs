@@ -0,0 +1,189 @@
+/// A trait for enums with one variant holding the value a guard wants, which
+/// can name the variant they are
+///
+/// This is used by [`try_variant!`](crate::try_variant) to report which
+/// variant made a guard fail.
+///
+/// ```
+/// use try_utils::VariantName;
+///
+/// enum Reply {
+///     Data(Vec<u8>),
+///     Busy,
+///     Closed { code: u32 },
+/// }
+///
+/// impl VariantName for Reply {
+///     type Output = Vec<u8>;
+///
+///     fn variant_name(&self) -> &'static str {
+///         match self {
+///             Reply::Data(_) => "Data",
+///             Reply::Busy => "Busy",
+///             Reply::Closed { .. } => "Closed",
+///         }
+///     }
+///
+///     fn into_success(self) -> Result<Vec<u8>, Self> {
+///         match self {
+///             Reply::Data(data) => Ok(data),
+///             other => Err(other),
+///         }
+///     }
+/// }
+///
+/// assert_eq!(Reply::Closed { code: 1 }.variant_name(), "Closed");
+/// assert_eq!(Reply::Data(vec![1]).into_success().ok(), Some(vec![1]));
+/// ```
+pub trait VariantName: Sized {
+    /// The value held by the success variant
+    type Output;
+
+    /// Returns the name of this variant
+    fn variant_name(&self) -> &'static str;
+
+    /// Returns the value if this is the success variant, otherwise `self`
+    fn into_success(self) -> Result<Self::Output, Self>;
+}
+
+impl<T> VariantName for Option<T> {
+    type Output = T;
+
+    fn variant_name(&self) -> &'static str {
+        match self {
+            Some(_) => "Some",
+            None => "None",
+        }
+    }
+
+    fn into_success(self) -> Result<T, Self> {
+        self.ok_or(None)
+    }
+}
+
+impl<T, E> VariantName for Result<T, E> {
+    type Output = T;
+
+    fn variant_name(&self) -> &'static str {
+        match self {
+            Ok(_) => "Ok",
+            Err(_) => "Err",
+        }
+    }
+
+    fn into_success(self) -> Result<T, Self> {
+        self.map_err(Err)
+    }
+}
+
+/// Returns the value of the success variant of a [`VariantName`] enum,
+/// otherwise returns from the current function with `Err` of the name of the
+/// variant it was, converted with `From`.
+///
+/// A closure can be given instead, which is called with the variant name and
+/// builds the return value, for example after logging the name.
+///
+/// ```
+/// use try_utils::try_variant;
+///
+/// fn first(val: Option<u32>) -> Result<u32, String> {
+///     let val = try_variant!(val);
+///     Ok(val)
+/// }
+/// assert_eq!(first(None), Err("None".to_owned()));
+///
+/// fn logged(val: Result<u32, ()>) -> u32 {
+///     try_variant!(val, |name| {
+///         eprintln!("guard failed on {}", name);
+///         0
+///     })
+/// }
+/// assert_eq!(logged(Err(())), 0);
+/// ```
+///
+/// [`VariantName`]: crate::VariantName
+#[macro_export]
+macro_rules! try_variant {
+    ($e: expr) => {
+        $crate::try_variant!($e, |name| {
+            ::core::result::Result::Err(::core::convert::From::from(name))
+        })
+    };
+
+    ($e: expr, $ret: expr) => {
+        match $crate::VariantName::into_success($e) {
+            ::core::result::Result::Ok(v) => v,
+            ::core::result::Result::Err(other) => {
+                let name: &'static str = $crate::VariantName::variant_name(&other);
+                return $crate::__call(name, $ret);
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VariantName;
+
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        Key(char),
+        Resize(u16, u16),
+        Focus,
+        Closed { code: i32 },
+    }
+
+    impl VariantName for Event {
+        type Output = char;
+
+        fn variant_name(&self) -> &'static str {
+            match self {
+                Event::Key(_) => "Key",
+                Event::Resize(..) => "Resize",
+                Event::Focus => "Focus",
+                Event::Closed { .. } => "Closed",
+            }
+        }
+
+        fn into_success(self) -> Result<char, Self> {
+            match self {
+                Event::Key(c) => Ok(c),
+                other => Err(other),
+            }
+        }
+    }
+
+    #[test]
+    fn try_variant_names() {
+        fn key(event: Event) -> Result<char, &'static str> {
+            Ok(try_variant!(event))
+        }
+
+        assert_eq!(key(Event::Key('a')), Ok('a'));
+        assert_eq!(key(Event::Resize(80, 24)), Err("Resize"));
+        assert_eq!(key(Event::Focus), Err("Focus"));
+        assert_eq!(key(Event::Closed { code: 1 }), Err("Closed"));
+
+        let mut skipped = Vec::new();
+        let mut keys = String::new();
+        for event in [Event::Key('h'), Event::Focus, Event::Key('i')] {
+            let mut push = |event| {
+                keys.push(try_variant!(event, |name| skipped.push(name)));
+            };
+            push(event);
+        }
+        assert_eq!(keys, "hi");
+        assert_eq!(skipped, vec!["Focus"]);
+    }
+
+    #[test]
+    fn try_variant_option_result() {
+        fn both(a: Option<u32>, b: Result<u32, ()>) -> Result<u32, String> {
+            Ok(try_variant!(a) + try_variant!(b))
+        }
+
+        assert_eq!(both(Some(1), Ok(2)), Ok(3));
+        assert_eq!(both(None, Ok(2)), Err("None".to_owned()));
+        assert_eq!(both(Some(1), Err(())), Err("Err".to_owned()));
+    }
+}
@@ -0,0 +1,131 @@
+use std::time::Instant;
+
+/// A summary of the failures of a guard: when it first and last failed and
+/// how many times
+///
+/// This is updated by `try_return!(expr, window: &mut window, ret)`.
+///
+/// ```
+/// use try_utils::{try_return, FailureWindow};
+///
+/// fn check(val: Option<u32>, window: &mut FailureWindow) -> u32 {
+///     try_return!(val, window: window, 0)
+/// }
+///
+/// let mut window = FailureWindow::new();
+/// check(None, &mut window);
+/// check(Some(1), &mut window);
+/// check(None, &mut window);
+/// assert_eq!(window.count(), 2);
+/// assert!(window.first().unwrap() <= window.last().unwrap());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FailureWindow {
+    first: Option<Instant>,
+    last: Option<Instant>,
+    count: u64,
+}
+
+impl FailureWindow {
+    /// Creates a window with no failures
+    pub const fn new() -> Self {
+        FailureWindow {
+            first: None,
+            last: None,
+            count: 0,
+        }
+    }
+
+    /// Records a failure happening now
+    pub fn record(&mut self) {
+        self.record_at(Instant::now());
+    }
+
+    /// Records a failure that happened at the given time
+    pub fn record_at(&mut self, at: Instant) {
+        self.first.get_or_insert(at);
+        self.last = Some(at);
+        self.count += 1;
+    }
+
+    /// Returns when the first failure was recorded
+    pub fn first(&self) -> Option<Instant> {
+        self.first
+    }
+
+    /// Returns when the most recent failure was recorded
+    pub fn last(&self) -> Option<Instant> {
+        self.last
+    }
+
+    /// Returns how many failures have been recorded
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Removes all recorded failures
+    pub fn clear(&mut self) {
+        *self = FailureWindow::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::try_return;
+
+    fn check(val: Result<u32, &str>, window: &mut FailureWindow) -> u32 {
+        try_return!(val, window: window, 0)
+    }
+
+    #[test]
+    fn try_return_window() {
+        let mut window = FailureWindow::new();
+        assert_eq!(check(Ok(1), &mut window), 1);
+        assert_eq!(window, FailureWindow::default());
+
+        let before = Instant::now();
+        assert_eq!(check(Err("a"), &mut window), 0);
+        let first = window.first().unwrap();
+        assert!(first >= before);
+        assert_eq!(window.last(), Some(first));
+        assert_eq!(window.count(), 1);
+
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(check(Ok(2), &mut window), 2);
+        assert_eq!(check(Err("b"), &mut window), 0);
+        assert_eq!(check(Err("c"), &mut window), 0);
+        assert_eq!(window.first(), Some(first));
+        assert!(window.last().unwrap() >= first + Duration::from_millis(2));
+        assert_eq!(window.count(), 3);
+
+        window.clear();
+        assert_eq!(window.count(), 0);
+        assert_eq!(window.first(), None);
+    }
+
+    #[test]
+    fn failure_window_record_at() {
+        let start = Instant::now();
+        let mut window = FailureWindow::new();
+        window.record_at(start + Duration::from_secs(1));
+        window.record_at(start + Duration::from_secs(5));
+        assert_eq!(window.first(), Some(start + Duration::from_secs(1)));
+        assert_eq!(window.last(), Some(start + Duration::from_secs(5)));
+        assert_eq!(window.count(), 2);
+    }
+
+    #[test]
+    fn try_return_window_unit() {
+        fn check(val: Option<u32>, window: &mut FailureWindow) {
+            let _ = try_return!(val, window: window);
+            panic!();
+        }
+
+        let mut window = FailureWindow::new();
+        check(None, &mut window);
+        assert_eq!(window.count(), 1);
+    }
+}